@@ -0,0 +1,92 @@
+//! Times tower target acquisition through the `TargetGrid` against checking every target,
+//! at endless mode scale
+//!
+//! Run with `cargo bench --bench target_grid`, each frame rebuilds the grid (like
+//! `rebuild_target_grid`) and then runs one range query per tower
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use bevy::prelude::*;
+use bevy_tower_defense::spatial::{TargetGrid, TARGET_GRID_CELL_SIZE};
+
+const FRAMES: u32 = 2000;
+/// Enemies and towers are scattered over a square this wide, centered on the origin
+const AREA: f32 = 40.0;
+const TOWER_RANGE: f32 = 4.0;
+
+/// Deterministic scatter of positions over `AREA`, differing per `seed`
+fn scatter(count: usize, seed: u32) -> Vec<Vec3> {
+    let mut state = seed.wrapping_mul(0x9e37_79b9) | 1;
+    let mut next = move || {
+        state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        ((state >> 8) as f32 / (1 << 24) as f32 - 0.5) * AREA
+    };
+
+    (0..count).map(|_| Vec3::new(next(), 0.5, next())).collect()
+}
+
+/// Nudges every enemy along, so each frame queries a slightly different layout
+fn step(enemies: &mut [(Entity, Vec3)], frame: u32) {
+    let offset = Vec3::new(0.01, 0.0, 0.005) * (frame % 100) as f32;
+    for (_, position) in enemies {
+        *position += offset;
+    }
+}
+
+fn brute_force(towers: &[Vec3], enemies: &mut [(Entity, Vec3)]) -> Duration {
+    let start = Instant::now();
+    for frame in 0..FRAMES {
+        step(enemies, frame);
+        for tower in towers {
+            let in_range: Vec<_> = enemies
+                .iter()
+                .filter(|(_, position)| position.distance(*tower) <= TOWER_RANGE)
+                .collect();
+            black_box(in_range);
+        }
+    }
+    start.elapsed() / FRAMES
+}
+
+fn grid(towers: &[Vec3], enemies: &mut [(Entity, Vec3)]) -> Duration {
+    let mut grid = TargetGrid::new(TARGET_GRID_CELL_SIZE);
+
+    let start = Instant::now();
+    for frame in 0..FRAMES {
+        step(enemies, frame);
+        grid.clear();
+        for (entity, position) in enemies.iter() {
+            grid.insert(*entity, *position);
+        }
+        for tower in towers {
+            black_box(grid.query_range(*tower, TOWER_RANGE));
+        }
+    }
+    start.elapsed() / FRAMES
+}
+
+fn main() {
+    println!("towers  enemies  brute force/frame  grid/frame  speedup");
+
+    for (tower_count, enemy_count) in [(10, 100), (30, 300), (50, 800), (80, 1500)] {
+        let towers = scatter(tower_count, 1);
+        let enemies: Vec<_> = scatter(enemy_count, 2)
+            .into_iter()
+            .enumerate()
+            .map(|(index, position)| (Entity::from_raw(index as u32), position))
+            .collect();
+
+        let brute_force = brute_force(&towers, &mut enemies.clone());
+        let grid = grid(&towers, &mut enemies.clone());
+
+        println!(
+            "{tower_count:>6}  {enemy_count:>7}  {:>17.1?}  {:>10.1?}  {:>6.1}x",
+            brute_force,
+            grid,
+            brute_force.as_secs_f64() / grid.as_secs_f64()
+        );
+    }
+}
@@ -2,7 +2,15 @@ use bevy::{pbr::NotShadowCaster, prelude::*, ui::FocusPolicy};
 use bevy_mod_picking::{Hover, PickableMesh};
 use leafwing_input_manager::prelude::*;
 
-use crate::{assets::GameAssets, common::Despawn, input::Action, spawn_tower, TowerType};
+use crate::{
+    common::Despawn,
+    hooks::SceneHook,
+    input::Action,
+    range_indicator::{RangeIndicatorAssets, RangeIndicatorBundle},
+    spawn_tower,
+    state::{GameState, SessionEntity},
+    Tower, TowerRegistry, TowerType, Wallet, TOWER_HEIGHT_OFFSET,
+};
 
 pub struct BuilderPlugin;
 
@@ -13,13 +21,20 @@ impl Plugin for BuilderPlugin {
             .register_type::<Builder>()
             .register_type::<BuilderHover>()
             .register_type::<BuilderBox>()
+            .register_type::<BuilderGhostPart>()
             .add_startup_system_to_stage(StartupStage::PreStartup, load_builder_assets)
-            .add_system(builder)
-            .add_system(mark_build_locations)
-            .add_system(show_builder_box_on_hover_enter)
-            .add_system(hide_builder_box_on_hover_leave)
-            .add_system(cancel_build)
-            .add_system(confirm_build);
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(builder)
+                    .with_system(mark_build_locations)
+                    .with_system(reopen_build_locations)
+                    .with_system(show_builder_box_on_hover_enter)
+                    .with_system(hide_builder_box_on_hover_leave)
+                    .with_system(validate_builder_box)
+                    .with_system(tint_builder_box)
+                    .with_system(cancel_build)
+                    .with_system(confirm_build),
+            );
     }
 }
 
@@ -29,8 +44,8 @@ impl Plugin for BuilderPlugin {
 #[derive(Resource)]
 pub struct BuilderAssets {
     pub build_location: Handle<Mesh>,
-    pub builder_box: Handle<Mesh>,
-    pub builder_box_color: Handle<StandardMaterial>,
+    pub builder_box_valid: Handle<StandardMaterial>,
+    pub builder_box_invalid: Handle<StandardMaterial>,
 }
 
 fn load_builder_assets(
@@ -40,8 +55,16 @@ fn load_builder_assets(
 ) {
     commands.insert_resource(BuilderAssets {
         build_location: meshes.add(Mesh::from(shape::Plane { size: 1.0 })),
-        builder_box: meshes.add(Mesh::from(shape::Cube { size: 0.95 })),
-        builder_box_color: materials.add(Color::rgba(0.49, 0.97, 1.0, 0.6).into()),
+        builder_box_valid: materials.add(StandardMaterial {
+            base_color: Color::rgba(0.2, 1.0, 0.3, 0.5),
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        }),
+        builder_box_invalid: materials.add(StandardMaterial {
+            base_color: Color::rgba(1.0, 0.2, 0.2, 0.5),
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        }),
     });
 }
 
@@ -59,7 +82,10 @@ pub struct PickableBuildLocation;
 
 #[derive(Reflect, Component, Default)]
 #[reflect(Component)]
-pub struct LocationBuilt;
+pub struct LocationBuilt {
+    /// The tower standing on this location
+    pub tower: Option<Entity>,
+}
 
 #[derive(Bundle)]
 pub struct PickableBuildLocationBundle {
@@ -98,6 +124,27 @@ fn mark_build_locations(
     }
 }
 
+/*
+    BUILT LOCATION => BUILD LOCATION (ONCE THE TOWER IS GONE)
+*/
+
+fn reopen_build_locations(
+    mut commands: Commands,
+    built_locations: Query<(Entity, &LocationBuilt)>,
+    towers: Query<(), (With<Tower>, Without<Despawn>)>,
+) {
+    for (entity, location_built) in built_locations.iter() {
+        let standing = location_built
+            .tower
+            .is_some_and(|tower| towers.contains(tower));
+
+        // the location is free to build on again
+        if !standing {
+            commands.entity(entity).remove::<LocationBuilt>();
+        }
+    }
+}
+
 /*
     BUILDER
 */
@@ -112,33 +159,56 @@ pub struct Builder {
 #[reflect(Component)]
 pub struct BuilderHover;
 
+/// Placement preview of the tower being built, tinted by whether it can go here
 #[derive(Reflect, Component, Default)]
 #[reflect(Component)]
-pub struct BuilderBox;
+pub struct BuilderBox {
+    pub valid: bool,
+}
 
-#[derive(Bundle, Reflect, Default)]
+/// Mesh of the builder box's tower scene, drawn with the builder box material
+#[derive(Reflect, Component)]
+#[reflect(Component)]
+pub struct BuilderGhostPart {
+    pub builder_box: Entity,
+}
+
+impl FromWorld for BuilderGhostPart {
+    fn from_world(_world: &mut World) -> Self {
+        Self {
+            builder_box: Entity::from_raw(u32::MAX),
+        }
+    }
+}
+
+#[derive(Bundle, Default)]
 pub struct BuilderBoxBundle {
     builder_box: BuilderBox,
-    #[reflect(ignore)]
-    pbr_bundle: PbrBundle,
-    no_shadow_caster: NotShadowCaster,
+    scene_bundle: SceneBundle,
 }
 
 impl BuilderBoxBundle {
-    pub fn new(assets: &Res<BuilderAssets>) -> Self {
+    pub fn new(scene: Handle<Scene>) -> Self {
         Self {
-            builder_box: BuilderBox,
-            pbr_bundle: PbrBundle {
-                mesh: assets.builder_box.clone(),
-                material: assets.builder_box_color.clone(),
-                transform: Transform::from_xyz(0.0, 0.5, 0.0),
+            builder_box: BuilderBox::default(),
+            scene_bundle: SceneBundle {
+                scene,
+                transform: Transform::from_xyz(0.0, TOWER_HEIGHT_OFFSET, 0.0),
                 ..default()
             },
-            no_shadow_caster: NotShadowCaster,
         }
     }
 }
 
+/// Swaps the tower scene's own materials out for the builder box material
+fn builder_box_scene_hook(builder_box: Entity) -> SceneHook {
+    SceneHook::new(move |entity, cmds| {
+        if entity.contains::<Handle<StandardMaterial>>() {
+            cmds.insert((BuilderGhostPart { builder_box }, NotShadowCaster));
+        }
+    })
+}
+
 fn builder(
     mut commands: Commands,
     interaction: Query<(&Interaction, &TowerType), Changed<Interaction>>,
@@ -154,7 +224,7 @@ fn builder(
             // Create new builder
             commands.spawn((
                 Builder {
-                    tower_type: tower_type.clone(),
+                    tower_type: *tower_type,
                 },
                 InputManagerBundle::<Action> {
                     // Stores "which actions are currently pressed"
@@ -166,29 +236,31 @@ fn builder(
                     ]),
                 },
                 Name::new("Builder"),
+                SessionEntity,
             ));
         }
     }
 }
 
+/// Build locations whose hover state changed this frame
+type BuildLocationHoverChanged = (With<PickableBuildLocation>, Changed<Interaction>);
+
 fn show_builder_box_on_hover_enter(
     mut commands: Commands,
-    build_tile_hovered: Query<
-        (Entity, &Hover),
-        (
-            With<PickableBuildLocation>,
-            Without<BuilderHover>,
-            Without<LocationBuilt>,
-            Changed<Interaction>,
-        ),
-    >,
+    build_tile_hovered: Query<(Entity, &Hover), (BuildLocationHoverChanged, Without<BuilderHover>)>,
     builder: Query<&Builder>,
-    assets: Res<BuilderAssets>,
+    range_indicator_assets: Res<RangeIndicatorAssets>,
+    tower_registry: Res<TowerRegistry>,
 ) {
     // If we don't have an active builder, exit
-    if builder.is_empty() {
-        return;
-    }
+    let builder = match builder.get_single() {
+        Ok(builder) => builder,
+        Err(_) => return,
+    };
+    let blueprint = match tower_registry.get(builder.tower_type) {
+        Some(blueprint) => blueprint,
+        None => return,
+    };
 
     // Place the selected tower at the build location
     for (entity, hover) in build_tile_hovered.iter() {
@@ -197,7 +269,19 @@ fn show_builder_box_on_hover_enter(
                 .entity(entity)
                 .insert(BuilderHover)
                 .with_children(|cmd| {
-                    cmd.spawn(BuilderBoxBundle::new(&assets));
+                    let mut builder_box = cmd.spawn(BuilderBoxBundle::new(blueprint.scene.clone()));
+                    let builder_box_entity = builder_box.id();
+
+                    builder_box
+                        .insert(builder_box_scene_hook(builder_box_entity))
+                        .with_children(|cmd| {
+                            // the box sits where the tower would, the ring goes on the tile
+                            cmd.spawn(RangeIndicatorBundle::new(
+                                &range_indicator_assets,
+                                blueprint.definition.range,
+                                -TOWER_HEIGHT_OFFSET,
+                            ));
+                        });
                 });
         }
     }
@@ -205,14 +289,7 @@ fn show_builder_box_on_hover_enter(
 
 fn hide_builder_box_on_hover_leave(
     mut commands: Commands,
-    build_tile_hovered: Query<
-        (Entity, &Hover),
-        (
-            With<PickableBuildLocation>,
-            Without<LocationBuilt>,
-            Changed<Interaction>,
-        ),
-    >,
+    build_tile_hovered: Query<(Entity, &Hover), BuildLocationHoverChanged>,
     build_placement_model: Query<Entity, With<BuilderBox>>,
 ) {
     for (entity, hover) in build_tile_hovered.iter() {
@@ -222,6 +299,55 @@ fn hide_builder_box_on_hover_leave(
     }
 }
 
+/// Placement is valid on a free build location when the tower is affordable
+fn validate_builder_box(
+    mut builder_boxes: Query<(&Parent, &mut BuilderBox)>,
+    build_locations: Query<(), With<LocationBuilt>>,
+    builder: Query<&Builder>,
+    tower_registry: Res<TowerRegistry>,
+    wallet: Res<Wallet>,
+) {
+    let builder = match builder.get_single() {
+        Ok(builder) => builder,
+        Err(_) => return,
+    };
+
+    // a tower type that isn't defined can never be built
+    let cost = tower_registry
+        .get(builder.tower_type)
+        .map(|blueprint| blueprint.definition.cost);
+
+    for (parent, mut builder_box) in &mut builder_boxes {
+        let valid = !build_locations.contains(parent.get())
+            && cost.is_some_and(|cost| wallet.can_afford(cost));
+        if builder_box.valid != valid {
+            builder_box.valid = valid;
+        }
+    }
+}
+
+fn tint_builder_box(
+    mut parts: Query<(&BuilderGhostPart, &mut Handle<StandardMaterial>)>,
+    builder_boxes: Query<&BuilderBox>,
+    assets: Res<BuilderAssets>,
+) {
+    for (part, mut material) in &mut parts {
+        let valid = builder_boxes
+            .get(part.builder_box)
+            .is_ok_and(|builder_box| builder_box.valid);
+
+        let tint = if valid {
+            &assets.builder_box_valid
+        } else {
+            &assets.builder_box_invalid
+        };
+
+        if *material != *tint {
+            *material = tint.clone();
+        }
+    }
+}
+
 fn cancel_build(
     mut commands: Commands,
     builder_query: Query<Entity, With<Builder>>,
@@ -250,11 +376,12 @@ fn confirm_build(
     builder_query: Query<(Entity, &Builder)>,
     builder_action_query: Query<&ActionState<Action>, With<Builder>>,
     build_tile_hovered: Query<
-        (Entity, &Hover, &GlobalTransform),
-        (With<PickableBuildLocation>, Without<LocationBuilt>),
+        (Entity, &Hover, &GlobalTransform, Option<&LocationBuilt>),
+        With<PickableBuildLocation>,
     >,
     build_placement_model: Query<Entity, With<BuilderBox>>,
-    assets: Res<GameAssets>,
+    tower_registry: Res<TowerRegistry>,
+    mut wallet: ResMut<Wallet>,
 ) {
     // If we don't have a builder or builder_actions, exit
     if builder_query.is_empty() || builder_action_query.is_empty() {
@@ -265,25 +392,48 @@ fn confirm_build(
     let action_state = builder_action_query.single();
 
     if action_state.just_pressed(Action::BuildTowerConfirm) {
-        for (entity, hover, transform) in build_tile_hovered.iter() {
+        for (entity, hover, transform, location_built) in build_tile_hovered.iter() {
             if hover.hovered() {
+                // refuse to build on top of another tower
+                if location_built.is_some() {
+                    info!("Build location is already occupied");
+                    return;
+                }
+
+                let blueprint = match tower_registry.get(builder.tower_type) {
+                    Some(blueprint) => blueprint,
+                    None => {
+                        warn!("No tower definition for {:?}", builder.tower_type);
+                        return;
+                    }
+                };
+
+                // refuse to build if we can't pay for the tower
+                let cost = blueprint.definition.cost;
+                if !wallet.try_spend(cost) {
+                    info!(
+                        "Not enough gold to build tower ({} < {})",
+                        wallet.gold, cost
+                    );
+                    return;
+                }
+
                 // remove hover components and entities
                 remove_hover(&mut commands, entity, &build_placement_model);
 
-                // insert LocationBuilt and remove marked build location
-                commands
-                    .entity(entity)
-                    .insert(LocationBuilt)
-                    .remove::<PickableBuildLocationBundle>();
-
                 // spawn the tower
-                spawn_tower(
+                let tower = spawn_tower(
                     &mut commands,
                     builder.tower_type,
-                    &assets,
+                    blueprint,
                     transform.translation(),
                 );
 
+                // mark the location as built, it stays pickable so the ghost can show it's taken
+                commands
+                    .entity(entity)
+                    .insert(LocationBuilt { tower: Some(tower) });
+
                 // remove the builder
                 commands.entity(builder_entity).insert(Despawn);
             }
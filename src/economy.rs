@@ -0,0 +1,69 @@
+use bevy::prelude::*;
+
+use crate::{level::LevelSettings, state::GameState};
+
+pub struct EconomyPlugin;
+
+impl Plugin for EconomyPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Wallet>()
+            .register_type::<SellRefund>()
+            .init_resource::<Wallet>()
+            .init_resource::<SellRefund>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_wallet));
+    }
+}
+
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct Wallet {
+    pub gold: u32,
+}
+
+impl Wallet {
+    pub fn new(gold: u32) -> Self {
+        Self { gold }
+    }
+
+    pub fn can_afford(&self, cost: u32) -> bool {
+        self.gold >= cost
+    }
+
+    /// Deducts `cost` from the wallet, returns false (and leaves the wallet untouched)
+    /// if there is not enough gold to cover it
+    pub fn try_spend(&mut self, cost: u32) -> bool {
+        if !self.can_afford(cost) {
+            return false;
+        }
+
+        self.gold -= cost;
+        true
+    }
+
+    pub fn earn(&mut self, amount: u32) {
+        self.gold += amount;
+    }
+}
+
+/// Share of the gold invested in a tower that is paid back when it is sold, set from the
+/// tower definitions whenever they (re)load
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct SellRefund {
+    pub percent: u32,
+}
+
+impl SellRefund {
+    pub fn new(percent: u32) -> Self {
+        Self { percent }
+    }
+
+    /// Gold paid back for a tower that cost `invested` in total, rounded down
+    pub fn refund(&self, invested: u32) -> u32 {
+        invested * self.percent / 100
+    }
+}
+
+fn reset_wallet(mut wallet: ResMut<Wallet>, settings: Res<LevelSettings>) {
+    *wallet = Wallet::new(settings.starting_gold);
+}
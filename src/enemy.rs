@@ -1,10 +1,15 @@
-use bevy::prelude::*;
+use bevy::{asset::HandleId, prelude::*, reflect::TypeUuid, utils::HashMap};
+use serde::Deserialize;
 
 use crate::{
-    assets::GameAssets,
-    common::{Health, Target},
+    common::{apply_health_requests, Died, Health, Target},
+    damage::Defense,
+    data::{ensure_non_negative, ensure_positive, RonAssetLoader, Validate},
     navigation::NavAgent,
     physics::PhysicsBundle,
+    state::{GameState, SessionEntity},
+    status::StatusEffects,
+    Wallet,
 };
 
 pub struct EnemyPlugin;
@@ -12,15 +17,145 @@ pub struct EnemyPlugin;
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<EnemyType>()
-            .register_type::<EnemyBundle>();
+            .register_type::<EnemyBundle>()
+            .add_asset::<EnemyDefinitions>()
+            .add_asset_loader(RonAssetLoader::<EnemyDefinitions>::new(&["enemies.ron"]))
+            .add_system(build_enemy_registry)
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(enemy_bounty.after(apply_health_requests)),
+            );
     }
 }
 
-#[derive(Reflect, Component, Clone, Copy, Default)]
+/// Key into the `EnemyRegistry`, matches the `enemy_type` of an enemy definition
+#[derive(
+    Reflect, FromReflect, Component, Deserialize, Clone, Default, PartialEq, Eq, Hash, Debug,
+)]
 #[reflect(Component)]
-pub enum EnemyType {
-    #[default]
-    EnemyBasic,
+#[serde(transparent)]
+pub struct EnemyType(pub String);
+
+impl EnemyType {
+    pub fn new(key: &str) -> Self {
+        Self(key.to_string())
+    }
+}
+
+/*
+    ENEMY DEFINITIONS (LOADED FROM ASSETS)
+*/
+
+#[derive(Deserialize, Clone, Copy)]
+pub enum ColliderShape {
+    Sphere { radius: f32 },
+    Cuboid { size: [f32; 3] },
+}
+
+impl ColliderShape {
+    fn validate(&self, owner: &str) -> Result<(), String> {
+        match self {
+            ColliderShape::Sphere { radius } => ensure_positive(owner, "collider radius", *radius),
+            ColliderShape::Cuboid { size } => size
+                .iter()
+                .try_for_each(|extent| ensure_positive(owner, "collider size", *extent)),
+        }
+    }
+}
+
+/// Designer tunable enemy archetype, see `assets/data/default.enemies.ron`
+#[derive(Deserialize, Clone)]
+pub struct EnemyDefinition {
+    pub enemy_type: EnemyType,
+    pub name: String,
+    pub health: i32,
+    pub move_speed: f32,
+    pub turn_speed: f32,
+    pub collider: ColliderShape,
+    /// Armor and resistances, takes full damage from everything if left out
+    #[serde(default)]
+    pub defense: Defense,
+    pub scene: String,
+    /// Gold earned for killing this enemy
+    pub bounty: u32,
+    /// Lives lost when this enemy reaches the end of its route
+    pub leak_damage: u32,
+}
+
+#[derive(Deserialize, TypeUuid)]
+#[uuid = "c3a0e5d2-1f4b-4d6a-b8e7-5a9d2c7e4f30"]
+pub struct EnemyDefinitions {
+    pub enemies: Vec<EnemyDefinition>,
+}
+
+impl Validate for EnemyDefinitions {
+    fn validate(&self) -> Result<(), String> {
+        for enemy in &self.enemies {
+            let name = enemy.name.as_str();
+            ensure_positive(name, "health", enemy.health as f32)?;
+            ensure_non_negative(name, "move_speed", enemy.move_speed)?;
+            ensure_non_negative(name, "turn_speed", enemy.turn_speed)?;
+            enemy.collider.validate(name)?;
+            enemy.defense.validate(name)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// An enemy definition along with the handles of the assets it references
+pub struct EnemyBlueprint {
+    pub definition: EnemyDefinition,
+    pub scene: Handle<Scene>,
+}
+
+/// Lookup of enemy blueprints by type, rebuilt whenever the definitions asset (re)loads
+#[derive(Resource, Default)]
+pub struct EnemyRegistry {
+    enemies: HashMap<EnemyType, EnemyBlueprint>,
+}
+
+impl EnemyRegistry {
+    /// `None` if the loaded definitions don't include `enemy_type`
+    pub fn get(&self, enemy_type: &EnemyType) -> Option<&EnemyBlueprint> {
+        self.enemies.get(enemy_type)
+    }
+
+    pub fn handles(&self) -> impl Iterator<Item = HandleId> + '_ {
+        self.enemies.values().map(|enemy| enemy.scene.id())
+    }
+}
+
+fn build_enemy_registry(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<EnemyDefinitions>>,
+    definitions: Res<Assets<EnemyDefinitions>>,
+    asset_server: Res<AssetServer>,
+) {
+    for event in events.iter() {
+        let handle = match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => handle,
+            AssetEvent::Removed { .. } => continue,
+        };
+
+        let definitions = match definitions.get(handle) {
+            Some(definitions) => definitions,
+            None => continue,
+        };
+
+        let mut registry = EnemyRegistry::default();
+        for definition in &definitions.enemies {
+            registry.enemies.insert(
+                definition.enemy_type.clone(),
+                EnemyBlueprint {
+                    definition: definition.clone(),
+                    scene: asset_server.load(definition.scene.as_str()),
+                },
+            );
+        }
+
+        commands.insert_resource(registry);
+    }
 }
 
 #[derive(Default, Bundle, Reflect)]
@@ -28,6 +163,8 @@ pub struct EnemyBundle {
     target: Target,
     health: Health,
     nav_agent: NavAgent,
+    status_effects: StatusEffects,
+    defense: Defense,
     #[reflect(ignore)]
     physics_bundle: PhysicsBundle,
 }
@@ -38,18 +175,22 @@ impl EnemyBundle {
         move_speed: f32,
         turn_speed: f32,
         route: Vec<Vec3>,
+        defense: Defense,
         physics_bundle: PhysicsBundle,
     ) -> Self {
         Self {
             target: Target,
-            health: Health { value: health },
+            health: Health::new(health),
             nav_agent: NavAgent {
                 move_speed,
                 turn_speed,
                 delay_timer: Timer::from_seconds(0.5, TimerMode::Once),
+                // head for the first waypoint until the agent is enabled
+                destination: route.last().copied().unwrap_or_default(),
                 route,
-                ..default()
             },
+            status_effects: StatusEffects::new(move_speed),
+            defense,
             physics_bundle,
         }
     }
@@ -58,29 +199,51 @@ impl EnemyBundle {
 pub fn spawn_enemy(
     commands: &mut Commands,
     location: Vec3,
-    enemy_type: EnemyType,
-    nav_route: &Vec<Vec3>,
-    game_assets: &Res<GameAssets>,
+    enemy_type: &EnemyType,
+    nav_route: &[Vec3],
+    blueprint: &EnemyBlueprint,
 ) {
-    match enemy_type {
-        EnemyType::EnemyBasic => {
-            commands.spawn((
-                SceneBundle {
-                    scene: game_assets.ufo_red_scene.clone(),
-                    transform: Transform::from_translation(location)
-                        .looking_at(*nav_route.last().unwrap(), Vec3::Y),
-                    ..default()
-                },
-                EnemyBundle::new(
-                    3,
-                    1.2,
-                    2.4,
-                    nav_route.clone(),
-                    PhysicsBundle::moving_entity_sphere(0.55),
-                ),
-                Name::new("Enemy (Basic)"),
-                EnemyType::EnemyBasic,
-            ));
+    let definition = &blueprint.definition;
+
+    let physics_bundle = match definition.collider {
+        ColliderShape::Sphere { radius } => PhysicsBundle::moving_entity_sphere(radius),
+        ColliderShape::Cuboid { size } => PhysicsBundle::moving_entity_cube(Vec3::from(size)),
+    };
+
+    commands.spawn((
+        SceneBundle {
+            scene: blueprint.scene.clone(),
+            transform: Transform::from_translation(location)
+                .looking_at(*nav_route.last().unwrap(), Vec3::Y),
+            ..default()
+        },
+        EnemyBundle::new(
+            definition.health,
+            definition.move_speed,
+            definition.turn_speed,
+            nav_route.to_vec(),
+            definition.defense.clone(),
+            physics_bundle,
+        ),
+        Name::new(format!("Enemy ({})", definition.name)),
+        enemy_type.clone(),
+        SessionEntity,
+    ));
+}
+
+fn enemy_bounty(
+    mut died: EventReader<Died>,
+    enemies: Query<&EnemyType>,
+    registry: Res<EnemyRegistry>,
+    mut wallet: ResMut<Wallet>,
+) {
+    for event in died.iter() {
+        if let Some(blueprint) = enemies
+            .get(event.entity)
+            .ok()
+            .and_then(|enemy_type| registry.get(enemy_type))
+        {
+            wallet.earn(blueprint.definition.bounty);
         }
     }
 }
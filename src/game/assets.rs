@@ -1,32 +1,76 @@
-use bevy::prelude::*;
+use bevy::{asset::LoadState, prelude::*};
+
+use crate::{
+    level::LevelDefinition, state::GameState, EnemyDefinitions, EnemyRegistry, TowerDefinitions,
+    TowerRegistry,
+};
 
 pub struct AssetsPlugin;
 
 impl Plugin for AssetsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system_to_stage(StartupStage::PreStartup, load_game_assets);
+        app.add_startup_system_to_stage(StartupStage::PreStartup, load_game_assets)
+            .add_system_set(
+                SystemSet::on_update(GameState::Loading).with_system(check_game_assets_loaded),
+            );
     }
 }
 
 #[derive(Resource)]
 pub struct GameAssets {
     pub level_0: Handle<Scene>,
+    pub level_0_definition: Handle<LevelDefinition>,
     pub tower_scene: Handle<Scene>,
-    pub tower_cannon_scene: Handle<Scene>,
-    pub tower_catapult_scene: Handle<Scene>,
-    pub tower_blaster_scene: Handle<Scene>,
-    pub cannon_ball_scene: Handle<Scene>,
-    pub ufo_red_scene: Handle<Scene>,
+    pub tower_definitions: Handle<TowerDefinitions>,
+    pub enemy_definitions: Handle<EnemyDefinitions>,
 }
 
 fn load_game_assets(mut commands: Commands, assets: Res<AssetServer>) {
     commands.insert_resource(GameAssets {
         level_0: assets.load("model/Level_0.glb#Scene0"),
+        level_0_definition: assets.load("data/level_0.level.ron"),
         tower_scene: assets.load("model/Tower.glb#Scene0"),
-        tower_cannon_scene: assets.load("model/TowerCannon.glb#Scene0"),
-        tower_catapult_scene: assets.load("model/TowerCatapult.glb#Scene0"),
-        tower_blaster_scene: assets.load("model/TowerBlaster.glb#Scene0"),
-        cannon_ball_scene: assets.load("model/CannonBall.glb#Scene0"),
-        ufo_red_scene: assets.load("model/UfoRed.glb#Scene0"),
+        tower_definitions: assets.load("data/default.towers.ron"),
+        enemy_definitions: assets.load("data/default.enemies.ron"),
     });
 }
+
+fn check_game_assets_loaded(
+    game_assets: Res<GameAssets>,
+    tower_registry: Option<Res<TowerRegistry>>,
+    enemy_registry: Option<Res<EnemyRegistry>>,
+    assets: Res<AssetServer>,
+    mut state: ResMut<State<GameState>>,
+    mut reported_failure: Local<bool>,
+) {
+    // Definitions reference further assets, wait until they have been registered
+    let (tower_registry, enemy_registry) = match (tower_registry, enemy_registry) {
+        (Some(tower_registry), Some(enemy_registry)) => (tower_registry, enemy_registry),
+        _ => return,
+    };
+
+    let handles = [
+        game_assets.level_0.id(),
+        game_assets.level_0_definition.id(),
+        game_assets.tower_scene.id(),
+        game_assets.tower_definitions.id(),
+        game_assets.enemy_definitions.id(),
+    ]
+    .into_iter()
+    .chain(tower_registry.handles())
+    .chain(enemy_registry.handles());
+
+    match assets.get_group_load_state(handles) {
+        LoadState::Loaded => {
+            if let Err(err) = state.set(GameState::MainMenu) {
+                warn!("Leaving Loading failed: {:?}", err);
+            }
+        }
+        // nothing works without these, stay in Loading rather than carry on
+        LoadState::Failed if !*reported_failure => {
+            error!("Failed to load game assets!");
+            *reported_failure = true;
+        }
+        _ => {}
+    }
+}
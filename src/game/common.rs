@@ -1,5 +1,7 @@
 use bevy::prelude::*;
 
+use crate::state::GameState;
+
 pub struct CommonPlugin;
 
 impl Plugin for CommonPlugin {
@@ -7,22 +9,102 @@ impl Plugin for CommonPlugin {
         app.register_type::<Lifetime>()
             .register_type::<Health>()
             .register_type::<Target>()
-            .add_system(lifetime)
-            .add_system(death)
+            .register_type::<DamageStats>()
+            .add_event::<DamageRequest>()
+            .add_event::<HealRequest>()
+            .add_event::<Died>()
+            // Systems sending requests run `.before(apply_health_requests)`, so every request
+            // sent this frame lands in the same pass
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(lifetime)
+                    .with_system(apply_health_requests),
+            )
             .add_system_to_stage(CoreStage::PostUpdate, despawn);
     }
 }
 
+/// Only changed through `DamageRequest` and `HealRequest` events
 #[derive(Reflect, Component, Default)]
 #[reflect(Component)]
 pub struct Health {
-    pub value: i32,
+    pub current: i32,
+    pub max: i32,
+}
+
+impl Health {
+    pub fn new(max: i32) -> Self {
+        Self { current: max, max }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0
+    }
+
+    /// Share of max health left, for health bars and the like
+    pub fn fraction(&self) -> f32 {
+        if self.max <= 0 {
+            return 0.0;
+        }
+
+        self.current.max(0) as f32 / self.max as f32
+    }
+
+    /// Subtracts `amount`, returning how much of it the remaining health absorbed
+    fn take_damage(&mut self, amount: i32) -> i32 {
+        let absorbed = amount.clamp(0, self.current.max(0));
+        self.current -= amount.max(0);
+        absorbed
+    }
+
+    /// Adds `amount` up to max health, returning how much was actually restored
+    fn heal(&mut self, amount: i32) -> i32 {
+        let healed = amount.clamp(0, (self.max - self.current).max(0));
+        self.current += healed;
+        healed
+    }
+}
+
+/// Request to damage `target`, `amount` is what is left after armor and resistances
+/// (see `damage::calculate_damage`). `source` is credited with the damage and any kill
+pub struct DamageRequest {
+    pub target: Entity,
+    pub amount: i32,
+    pub source: Option<Entity>,
+}
+
+pub struct HealRequest {
+    pub target: Entity,
+    pub amount: i32,
+}
+
+/// Sent once when something's health runs out, `killer` is the source of the final blow
+pub struct Died {
+    pub entity: Entity,
+    pub killer: Option<Entity>,
 }
 
 #[derive(Reflect, Component, Default)]
 #[reflect(Component)]
 pub struct Target;
 
+/// Running totals for anything that deals damage
+#[derive(Reflect, Component, Default)]
+#[reflect(Component)]
+pub struct DamageStats {
+    pub damage_dealt: u32,
+    pub kills: u32,
+}
+
+impl DamageStats {
+    pub fn record(&mut self, damage: i32, killed: bool) {
+        self.damage_dealt += damage.max(0) as u32;
+        if killed {
+            self.kills += 1;
+        }
+    }
+}
+
 #[derive(Reflect, Component, Default)]
 #[reflect(Component)]
 pub struct Lifetime {
@@ -33,10 +115,45 @@ pub struct Lifetime {
 #[reflect(Component)]
 pub struct Despawn;
 
-fn death(mut commands: Commands, targets: Query<(Entity, &Health)>) {
-    for (ent, health) in &targets {
-        if health.value <= 0 {
-            commands.entity(ent).insert(Despawn);
+pub fn apply_health_requests(
+    mut commands: Commands,
+    mut damage_requests: EventReader<DamageRequest>,
+    mut heal_requests: EventReader<HealRequest>,
+    mut died: EventWriter<Died>,
+    mut healths: Query<&mut Health, Without<Despawn>>,
+    mut sources: Query<&mut DamageStats>,
+) {
+    for request in damage_requests.iter() {
+        // the dead can't die twice, and leaked enemies are out of reach
+        let mut health = match healths.get_mut(request.target) {
+            Ok(health) if !health.is_dead() => health,
+            _ => continue,
+        };
+
+        let dealt = health.take_damage(request.amount);
+        let killed = health.is_dead();
+
+        if let Some(mut source) = request
+            .source
+            .and_then(|source| sources.get_mut(source).ok())
+        {
+            source.record(dealt, killed);
+        }
+
+        if killed {
+            commands.entity(request.target).insert(Despawn);
+            died.send(Died {
+                entity: request.target,
+                killer: request.source,
+            });
+        }
+    }
+
+    for request in heal_requests.iter() {
+        if let Ok(mut health) = healths.get_mut(request.target) {
+            if !health.is_dead() {
+                health.heal(request.amount);
+            }
         }
     }
 }
@@ -0,0 +1,144 @@
+use bevy::{prelude::*, utils::HashMap};
+use serde::Deserialize;
+
+use crate::data::ensure_non_negative;
+
+pub struct DamagePlugin;
+
+impl Plugin for DamagePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<DamageType>().register_type::<Defense>();
+    }
+}
+
+#[derive(Reflect, FromReflect, Deserialize, Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub enum DamageType {
+    #[default]
+    Physical,
+    Explosive,
+    Energy,
+    Fire,
+    Poison,
+}
+
+impl DamageType {
+    /// Armor only stops things that have to get through it
+    pub fn ignores_armor(&self) -> bool {
+        matches!(
+            self,
+            DamageType::Energy | DamageType::Fire | DamageType::Poison
+        )
+    }
+}
+
+/// How well something stands up to incoming damage
+#[derive(Reflect, Component, Deserialize, Clone, Default)]
+#[reflect(Component)]
+pub struct Defense {
+    /// Flat reduction to every hit of a type that doesn't ignore armor
+    #[serde(default)]
+    pub armor: f32,
+    /// Multiplier per damage type, anything not listed takes full damage
+    #[serde(default)]
+    pub resistances: HashMap<DamageType, f32>,
+}
+
+impl Defense {
+    /// Checked when definitions load, damage is clamped at zero so a negative value here
+    /// would quietly turn into immunity
+    pub fn validate(&self, owner: &str) -> Result<(), String> {
+        ensure_non_negative(owner, "armor", self.armor)?;
+
+        for (damage_type, resistance) in &self.resistances {
+            ensure_non_negative(owner, &format!("{damage_type:?} resistance"), *resistance)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn resistance(&self, damage_type: DamageType) -> f32 {
+        self.resistances.get(&damage_type).copied().unwrap_or(1.0)
+    }
+}
+
+/// Damage left of `amount` once `defense` has had its say, armor first then resistance.
+/// Left fractional so damage over time can accumulate it, round it for a single hit
+pub fn calculate_damage(amount: f32, damage_type: DamageType, defense: Option<&Defense>) -> f32 {
+    let defense = match defense {
+        Some(defense) => defense,
+        None => return amount.max(0.0),
+    };
+
+    let armor = if damage_type.ignores_armor() {
+        0.0
+    } else {
+        defense.armor
+    };
+
+    ((amount - armor) * defense.resistance(damage_type)).max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defense(armor: f32, resistances: &[(DamageType, f32)]) -> Defense {
+        Defense {
+            armor,
+            resistances: resistances.iter().copied().collect(),
+        }
+    }
+
+    #[test]
+    fn no_defense_takes_full_damage() {
+        assert_eq!(calculate_damage(5.0, DamageType::Physical, None), 5.0);
+    }
+
+    #[test]
+    fn armor_comes_off_before_resistance() {
+        let defense = defense(2.0, &[(DamageType::Physical, 0.5)]);
+
+        // (10 - 2) * 0.5, not 10 * 0.5 - 2
+        assert_eq!(
+            calculate_damage(10.0, DamageType::Physical, Some(&defense)),
+            4.0
+        );
+    }
+
+    #[test]
+    fn armor_ignoring_types_only_face_resistance() {
+        let defense = defense(3.0, &[(DamageType::Fire, 0.5)]);
+
+        assert_eq!(calculate_damage(4.0, DamageType::Fire, Some(&defense)), 2.0);
+        assert_eq!(
+            calculate_damage(4.0, DamageType::Energy, Some(&defense)),
+            4.0
+        );
+        assert_eq!(
+            calculate_damage(4.0, DamageType::Explosive, Some(&defense)),
+            1.0
+        );
+    }
+
+    #[test]
+    fn damage_never_goes_below_zero() {
+        let defense = defense(5.0, &[]);
+
+        assert_eq!(
+            calculate_damage(2.0, DamageType::Physical, Some(&defense)),
+            0.0
+        );
+        assert_eq!(calculate_damage(-1.0, DamageType::Physical, None), 0.0);
+    }
+
+    #[test]
+    fn negative_armor_and_resistances_are_rejected() {
+        assert!(defense(0.0, &[(DamageType::Fire, 0.0)])
+            .validate("Enemy")
+            .is_ok());
+        assert!(defense(-1.0, &[]).validate("Enemy").is_err());
+        assert!(defense(0.0, &[(DamageType::Poison, -0.5)])
+            .validate("Enemy")
+            .is_err());
+    }
+}
@@ -0,0 +1,72 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    asset::{Asset, AssetLoader, LoadContext, LoadedAsset},
+    utils::BoxedFuture,
+};
+use serde::de::DeserializeOwned;
+
+/// Sanity checks on designer data, run by `RonAssetLoader` so bad values fail the load
+/// instead of blowing up (or silently misbehaving) once they're in the game
+pub trait Validate {
+    /// Describes the first problem found, if any
+    fn validate(&self) -> Result<(), String>;
+}
+
+/// Fails unless `value` is greater than zero, `owner` and `field` say where it came from
+pub fn ensure_positive(owner: &str, field: &str, value: f32) -> Result<(), String> {
+    if value > 0.0 {
+        Ok(())
+    } else {
+        Err(format!("{owner}: {field} must be positive, got {value}"))
+    }
+}
+
+/// Fails if `value` is negative (or NaN), `owner` and `field` say where it came from
+pub fn ensure_non_negative(owner: &str, field: &str, value: f32) -> Result<(), String> {
+    if value >= 0.0 {
+        Ok(())
+    } else {
+        Err(format!("{owner}: {field} can't be negative, got {value}"))
+    }
+}
+
+/// Loads any deserializable asset from a RON file with the given extension(s), rejecting
+/// it if it doesn't pass validation
+pub struct RonAssetLoader<T> {
+    extensions: &'static [&'static str],
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> RonAssetLoader<T> {
+    pub fn new(extensions: &'static [&'static str]) -> Self {
+        Self {
+            extensions,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> AssetLoader for RonAssetLoader<T>
+where
+    T: Asset + DeserializeOwned + Validate,
+{
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let asset = ron::de::from_bytes::<T>(bytes)?;
+            asset.validate().map_err(|err| {
+                bevy::asset::Error::msg(format!("{}: {}", load_context.path().display(), err))
+            })?;
+            load_context.set_default_asset(LoadedAsset::new(asset));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
+}
@@ -5,7 +5,17 @@ pub struct InputPlugin;
 
 impl Plugin for InputPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(InputManagerPlugin::<Action>::default());
+        app.add_plugin(InputManagerPlugin::<Action>::default())
+            // Global actions, not tied to any specific entity
+            .init_resource::<ActionState<Action>>()
+            .insert_resource(
+                InputMap::new([
+                    (KeyCode::P, Action::TogglePause),
+                    (KeyCode::Escape, Action::ClearSelection),
+                ])
+                .insert(MouseButton::Right, Action::ClearSelection)
+                .build(),
+            );
     }
 }
 
@@ -19,4 +29,6 @@ pub enum Action {
     CameraRotateRight,
     BuildTowerConfirm,
     BuildTowerCancel,
+    TogglePause,
+    ClearSelection,
 }
@@ -0,0 +1,259 @@
+use bevy::prelude::*;
+
+/// Smallest root of `a*t^2 + b*t + c = 0` within `[min, max]`
+fn first_root_in(a: f32, b: f32, c: f32, min: f32, max: f32) -> Option<f32> {
+    let in_window = |t: f32| t >= min && t <= max;
+
+    // degenerate case (projectile speed == target speed), the equation is linear
+    if a.abs() < f32::EPSILON {
+        if b.abs() < f32::EPSILON {
+            return None;
+        }
+        return Some(-c / b).filter(|t| in_window(*t));
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let sqrt_discriminant = discriminant.sqrt();
+    let t1 = (-b - sqrt_discriminant) / (2.0 * a);
+    let t2 = (-b + sqrt_discriminant) / (2.0 * a);
+
+    [t1.min(t2), t1.max(t2)].into_iter().find(|t| in_window(*t))
+}
+
+/// Time at which a projectile fired from `origin` at `speed` meets a target that is at
+/// `position` at time zero and moves with constant `velocity`, restricted to `[min, max]`
+///
+/// Solves |position + velocity * t - origin| = speed * t for t
+pub fn intercept_time(
+    origin: Vec3,
+    speed: f32,
+    position: Vec3,
+    velocity: Vec3,
+    min: f32,
+    max: f32,
+) -> Option<f32> {
+    let offset = position - origin;
+
+    first_root_in(
+        velocity.length_squared() - speed * speed,
+        2.0 * offset.dot(velocity),
+        offset.length_squared(),
+        min,
+        max,
+    )
+}
+
+/// Point at which a projectile fired from `origin` at `speed` meets a target at `position`
+/// that waits there for `delay` seconds, then follows the `path` waypoints at `move_speed`
+///
+/// Each leg of the path is solved as a constant velocity intercept over the time window
+/// the target spends on that leg, once the path runs out the target is treated as stationary
+pub fn route_intercept(
+    origin: Vec3,
+    speed: f32,
+    position: Vec3,
+    move_speed: f32,
+    delay: f32,
+    path: impl IntoIterator<Item = Vec3>,
+) -> Option<Vec3> {
+    let delay = delay.max(0.0);
+
+    // the shot gets there before the target sets off
+    if position.distance(origin) / speed <= delay {
+        return Some(position);
+    }
+
+    let mut leg_start = position;
+    let mut leg_start_time = delay;
+
+    for waypoint in path {
+        let leg = waypoint - leg_start;
+        let leg_length = leg.length();
+        if leg_length < f32::EPSILON || move_speed <= 0.0 {
+            continue;
+        }
+
+        let velocity = leg / leg_length * move_speed;
+        let leg_end_time = leg_start_time + leg_length / move_speed;
+
+        // where the target would have been at t = 0 had it always moved along this leg
+        let virtual_position = leg_start - velocity * leg_start_time;
+
+        if let Some(t) = intercept_time(
+            origin,
+            speed,
+            virtual_position,
+            velocity,
+            leg_start_time,
+            leg_end_time,
+        ) {
+            return Some(virtual_position + velocity * t);
+        }
+
+        leg_start = waypoint;
+        leg_start_time = leg_end_time;
+    }
+
+    // the target has stopped at the end of its path, reachable only once it gets there
+    let t = leg_start.distance(origin) / speed;
+    (t >= leg_start_time).then_some(leg_start)
+}
+
+/// Where a target at `position` that waits there for `delay` seconds, then follows the `path`
+/// waypoints at `move_speed` will be after `time`, stopping at the end of the path
+pub fn route_position(
+    position: Vec3,
+    move_speed: f32,
+    delay: f32,
+    path: impl IntoIterator<Item = Vec3>,
+    time: f32,
+) -> Vec3 {
+    let mut current = position;
+    let mut distance_left = move_speed.max(0.0) * (time - delay.max(0.0)).max(0.0);
+
+    for waypoint in path {
+        let leg_length = current.distance(waypoint);
+        if leg_length >= distance_left {
+            return current + (waypoint - current) / leg_length.max(f32::EPSILON) * distance_left;
+        }
+
+        distance_left -= leg_length;
+        current = waypoint;
+    }
+
+    current
+}
+
+/// Launch velocity (and flight time) for a projectile pulled down by `gravity` that travels
+/// from `start` to `end`, peaking `arc_height` above the higher of the two
+pub fn ballistic_launch(start: Vec3, end: Vec3, gravity: f32, arc_height: f32) -> (Vec3, f32) {
+    let apex = start.y.max(end.y) + arc_height.max(0.0);
+
+    // up to the apex and back down, each half is a plain free fall
+    let time_up = (2.0 * (apex - start.y) / gravity).sqrt();
+    let time_down = (2.0 * (apex - end.y) / gravity).sqrt();
+    let flight_time = (time_up + time_down).max(f32::EPSILON);
+
+    let horizontal = Vec3::new(end.x - start.x, 0.0, end.z - start.z) / flight_time;
+    (horizontal + Vec3::Y * gravity * time_up, flight_time)
+}
+
+/// Point at which a ballistic projectile launched from `origin` (see `ballistic_launch`) lands
+/// on a target at `position` that waits there for `delay` seconds, then follows the `path`
+/// waypoints at `move_speed`
+///
+/// The flight time barely depends on where the shot lands, so a few rounds of predicting the
+/// target's position for the current flight time settle quickly
+pub fn ballistic_intercept(
+    origin: Vec3,
+    gravity: f32,
+    arc_height: f32,
+    position: Vec3,
+    move_speed: f32,
+    delay: f32,
+    path: impl IntoIterator<Item = Vec3> + Clone,
+) -> Vec3 {
+    const ITERATIONS: usize = 4;
+
+    let mut landing = position;
+    for _ in 0..ITERATIONS {
+        let (_, flight_time) = ballistic_launch(origin, landing, gravity, arc_height);
+        landing = route_position(position, move_speed, delay, path.clone(), flight_time);
+    }
+
+    landing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-4;
+
+    #[test]
+    fn stationary_target_is_hit_where_it_stands() {
+        let t = intercept_time(
+            Vec3::ZERO,
+            2.0,
+            Vec3::new(4.0, 0.0, 0.0),
+            Vec3::ZERO,
+            0.0,
+            10.0,
+        );
+        assert!((t.unwrap() - 2.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn target_outrunning_the_projectile_cannot_be_caught() {
+        let t = intercept_time(
+            Vec3::ZERO,
+            1.0,
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(3.0, 0.0, 0.0),
+            0.0,
+            f32::MAX,
+        );
+        assert_eq!(t, None);
+    }
+
+    #[test]
+    fn intercept_follows_the_route_around_a_corner() {
+        // heads along +X to the corner, then turns down +Z
+        let position = Vec3::ZERO;
+        let corner = Vec3::new(2.0, 0.0, 0.0);
+        let path = [corner, Vec3::new(2.0, 0.0, 10.0)];
+        let origin = Vec3::new(5.0, 0.0, 5.0);
+        let speed = 2.0;
+
+        let intercept = route_intercept(origin, speed, position, 1.0, 0.0, path).unwrap();
+
+        // caught on the second leg, after the turn
+        assert!((intercept.x - 2.0).abs() < EPSILON);
+        assert!(intercept.z > 0.0);
+
+        // the shot and the target get there at the same time
+        let target_time = corner.distance(position) + intercept.distance(corner);
+        let shot_time = intercept.distance(origin) / speed;
+        assert!((target_time - shot_time).abs() < EPSILON);
+    }
+
+    #[test]
+    fn target_without_move_speed_is_treated_as_stationary() {
+        let position = Vec3::new(3.0, 0.0, 0.0);
+        let path = [Vec3::new(3.0, 0.0, 5.0)];
+
+        let intercept = route_intercept(Vec3::ZERO, 2.0, position, 0.0, 0.0, path);
+        assert_eq!(intercept, Some(position));
+    }
+
+    #[test]
+    fn target_is_led_from_where_it_sets_off_after_its_delay() {
+        let position = Vec3::new(2.0, 0.0, 0.0);
+        let path = [Vec3::new(2.0, 0.0, 10.0)];
+
+        // reaches it in 1s, well before the target starts moving
+        let waiting = route_intercept(Vec3::ZERO, 2.0, position, 1.0, 1.5, path);
+        assert_eq!(waiting, Some(position));
+
+        // the wait only shifts the moving part of the solution
+        let delayed = route_intercept(Vec3::ZERO, 2.0, position, 1.0, 0.5, path).unwrap();
+        let target_time = 0.5 + delayed.distance(position);
+        let shot_time = delayed.length() / 2.0;
+        assert!((target_time - shot_time).abs() < EPSILON);
+    }
+
+    #[test]
+    fn route_position_stops_at_the_end_of_the_path() {
+        let path = [Vec3::new(1.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 1.0)];
+
+        let midway = route_position(Vec3::ZERO, 1.0, 0.5, path, 2.0);
+        assert!(midway.distance(Vec3::new(1.0, 0.0, 0.5)) < EPSILON);
+
+        let end = route_position(Vec3::ZERO, 1.0, 0.0, path, 10.0);
+        assert!(end.distance(Vec3::new(1.0, 0.0, 1.0)) < EPSILON);
+    }
+}
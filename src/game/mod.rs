@@ -1,22 +1,33 @@
 use bevy::{app::PluginGroupBuilder, prelude::*};
 
 use self::{
-    assets::AssetsPlugin, common::CommonPlugin, input::InputPlugin, projectile::ProjectilePlugin,
+    assets::AssetsPlugin, common::CommonPlugin, damage::DamagePlugin, input::InputPlugin,
+    projectile::ProjectilePlugin, spatial::SpatialPlugin, state::StatePlugin, status::StatusPlugin,
 };
 
 pub mod assets;
 pub mod common;
+pub mod damage;
+pub mod data;
 pub mod input;
+pub mod intercept;
 pub mod physics;
 pub mod projectile;
+pub mod spatial;
+pub mod state;
+pub mod status;
 
 pub struct DefaultGamePlugins;
 impl PluginGroup for DefaultGamePlugins {
     fn build(self) -> PluginGroupBuilder {
         PluginGroupBuilder::start::<Self>()
+            .add(StatePlugin)
             .add(AssetsPlugin)
             .add(InputPlugin)
             .add(CommonPlugin)
+            .add(DamagePlugin)
             .add(ProjectilePlugin)
+            .add(SpatialPlugin)
+            .add(StatusPlugin)
     }
 }
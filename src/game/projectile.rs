@@ -1,7 +1,19 @@
-use bevy::prelude::*;
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticId, Diagnostics},
+    prelude::*,
+    utils::FloatOrd,
+};
 use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
 
-use crate::common::{Despawn, Health, Target};
+use crate::{
+    common::{apply_health_requests, DamageRequest, Despawn, Health, Target},
+    damage::{calculate_damage, DamageType, Defense},
+    data::ensure_positive,
+    spatial::{rebuild_target_grid, TargetGrid},
+    state::GameState,
+    status::{StatusEffect, StatusEffects},
+};
 
 #[derive(Reflect, Component, Default)]
 #[reflect(Component)]
@@ -9,36 +21,445 @@ pub struct Projectile {
     pub direction: Vec3,
     pub speed: f32,
     pub damage: i32,
+    pub damage_type: DamageType,
+    /// Whatever fired the projectile, credited with the damage it deals
+    pub origin: Option<Entity>,
+    /// Extra targets the projectile passes through before it is spent
+    pub pierce: u32,
+    /// Targets already damaged, each target is only hit once
+    pub hit: Vec<Entity>,
+    /// Applied to every target the projectile damages
+    pub effects: Vec<StatusEffect>,
 }
 
+impl Projectile {
+    /// Whether the projectile can still damage `target`
+    pub fn can_hit(&self, target: Entity) -> bool {
+        self.hit.len() <= self.pierce as usize && !self.hit.contains(&target)
+    }
+
+    /// Records a hit on `target`, returns true once the projectile has no pierce left
+    pub fn record_hit(&mut self, target: Entity) -> bool {
+        self.hit.push(target);
+        self.hit.len() > self.pierce as usize
+    }
+}
+
+/// Flies a projectile under its own gravity instead of in a straight line, rapier's gravity
+/// is turned off for the rest of the game
+#[derive(Reflect, Component, Default)]
+#[reflect(Component)]
+pub struct Ballistic {
+    pub velocity: Vec3,
+    pub gravity: f32,
+    /// Height the projectile detonates at on its way down
+    pub ground: f32,
+}
+
+/// Steers a projectile towards `target`, turning at most `turn_rate` radians per second
+#[derive(Reflect, Component, Default)]
+#[reflect(Component)]
+pub struct Homing {
+    pub target: Option<Entity>,
+    pub turn_rate: f32,
+    pub on_target_lost: TargetLost,
+}
+
+/// What a homing projectile does once its target is gone
+#[derive(Reflect, FromReflect, Deserialize, Clone, Copy, Default, Debug)]
+pub enum TargetLost {
+    /// Fly on in a straight line
+    #[default]
+    Continue,
+    /// Pick the closest target within `range` of the projectile, if any
+    Retarget { range: f32 },
+}
+
+/// Damages every target within `radius` when the projectile detonates
+#[derive(Reflect, FromReflect, Component, Deserialize, Clone, Copy, Default, Debug)]
+#[reflect(Component)]
+pub struct Splash {
+    pub radius: f32,
+    /// Share of the damage lost at the edge of the radius, 0 is full damage throughout
+    #[serde(default)]
+    pub falloff: f32,
+}
+
+impl Splash {
+    /// Checked when definitions load, the radius sizes the collider splash damage is found with
+    pub fn validate(&self, owner: &str) -> Result<(), String> {
+        ensure_positive(owner, "splash radius", self.radius)?;
+
+        if !(0.0..=1.0).contains(&self.falloff) {
+            return Err(format!(
+                "{owner}: splash falloff must be between 0 and 1, got {}",
+                self.falloff
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Damage dealt to a target `distance` away from the detonation
+    pub fn damage_at(&self, damage: f32, distance: f32) -> f32 {
+        let edge = (distance / self.radius).min(1.0);
+        let scale = 1.0 - self.falloff * edge;
+        damage * scale
+    }
+}
+
+/// A projectile touching a target, sent for every new contact rapier reports
+pub struct ProjectileHit {
+    pub projectile: Entity,
+    pub target: Entity,
+}
+
+/// Marks a projectile that has reached its destination and should apply its damage
+#[derive(Component)]
+pub struct Detonate;
+
+/// Running totals of projectiles fired and projectiles that damaged at least one target
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct ProjectileStats {
+    pub fired: u32,
+    pub hit: u32,
+}
+
+impl ProjectileStats {
+    /// Fraction of resolved projectiles that missed, `in_flight` projectiles are not counted
+    pub fn miss_rate(&self, in_flight: u32) -> f32 {
+        let resolved = self.fired.saturating_sub(in_flight);
+        if resolved == 0 {
+            return 0.0;
+        }
+
+        resolved.saturating_sub(self.hit) as f32 / resolved as f32
+    }
+}
+
+pub const PROJECTILE_MISS_RATE: DiagnosticId =
+    DiagnosticId::from_u128(251385346929127810387218473193245013412);
+
 pub struct ProjectilePlugin;
 
 impl Plugin for ProjectilePlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<Projectile>()
-            .add_system(move_projectiles)
-            .add_system(projectile_collision_detection);
+            .register_type::<ProjectileStats>()
+            .init_resource::<ProjectileStats>()
+            .add_startup_system(setup_projectile_diagnostics)
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(reset_projectile_stats),
+            )
+            .add_system(projectile_diagnostics)
+            .register_type::<Ballistic>()
+            .register_type::<Splash>()
+            .register_type::<Homing>()
+            .add_event::<ProjectileHit>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(
+                        steer_homing_projectiles
+                            .after(rebuild_target_grid)
+                            .before(move_projectiles),
+                    )
+                    .with_system(move_projectiles)
+                    .with_system(move_ballistic_projectiles)
+                    .with_system(detect_projectile_hits.before(resolve_projectile_hits))
+                    .with_system(resolve_projectile_hits.before(apply_health_requests))
+                    .with_system(splash_detonation.before(apply_health_requests)),
+            );
     }
 }
 
-fn move_projectiles(mut projectiles: Query<(&Projectile, &mut Transform)>, time: Res<Time>) {
+fn setup_projectile_diagnostics(mut diagnostics: ResMut<Diagnostics>) {
+    diagnostics
+        .add(Diagnostic::new(PROJECTILE_MISS_RATE, "projectile_miss_rate", 20).with_suffix("%"));
+}
+
+fn reset_projectile_stats(mut stats: ResMut<ProjectileStats>) {
+    *stats = ProjectileStats::default();
+}
+
+fn projectile_diagnostics(
+    mut diagnostics: ResMut<Diagnostics>,
+    stats: Res<ProjectileStats>,
+    projectiles: Query<(), With<Projectile>>,
+) {
+    let in_flight = projectiles.iter().count() as u32;
+    diagnostics.add_measurement(PROJECTILE_MISS_RATE, || {
+        stats.miss_rate(in_flight) as f64 * 100.0
+    });
+}
+
+fn move_projectiles(
+    mut projectiles: Query<(&Projectile, &mut Transform), Without<Ballistic>>,
+    time: Res<Time>,
+) {
     for (projectile, mut transform) in &mut projectiles {
         transform.translation +=
             projectile.direction.normalize() * projectile.speed * time.delta_seconds();
     }
 }
 
-fn projectile_collision_detection(
+fn steer_homing_projectiles(
+    mut projectiles: Query<(&mut Projectile, &mut Homing, &mut Transform), Without<Ballistic>>,
+    targets: Query<&GlobalTransform, (With<Target>, Without<Despawn>)>,
+    target_grid: Res<TargetGrid>,
+    time: Res<Time>,
+) {
+    for (mut projectile, mut homing, mut transform) in &mut projectiles {
+        // the target died mid-flight, or a piercing shot has gone through it
+        if homing
+            .target
+            .is_some_and(|target| !targets.contains(target) || !projectile.can_hit(target))
+        {
+            homing.target = match homing.on_target_lost {
+                TargetLost::Continue => None,
+                TargetLost::Retarget { range } => target_grid
+                    .query_range(transform.translation, range)
+                    .into_iter()
+                    .filter(|(target, _)| targets.contains(*target) && projectile.can_hit(*target))
+                    .min_by_key(|(_, position)| {
+                        FloatOrd(position.distance_squared(transform.translation))
+                    })
+                    .map(|(target, _)| target),
+            };
+        }
+
+        let target_position = match homing.target.and_then(|target| targets.get(target).ok()) {
+            Some(target_transform) => target_transform.translation(),
+            None => continue,
+        };
+
+        let current = projectile.direction.normalize();
+        let desired = (target_position - transform.translation).normalize();
+        let angle = current.angle_between(desired);
+        if !angle.is_finite() || angle < f32::EPSILON {
+            continue;
+        }
+
+        // turn towards the target, no further than the turn rate allows this frame
+        let max_turn = homing.turn_rate * time.delta_seconds();
+        let turn = Quat::from_rotation_arc(current, desired);
+        let direction = Quat::IDENTITY.slerp(turn, (max_turn / angle).min(1.0)) * current;
+
+        projectile.direction = direction;
+        let translation = transform.translation;
+        transform.look_at(translation + direction, Vec3::Y);
+    }
+}
+
+fn detect_projectile_hits(
+    mut collision_events: EventReader<CollisionEvent>,
+    mut projectile_hits: EventWriter<ProjectileHit>,
+    projectiles: Query<(), With<Projectile>>,
+    targets: Query<(), With<Target>>,
+) {
+    for event in collision_events.iter() {
+        let (a, b) = match event {
+            CollisionEvent::Started(a, b, _) => (*a, *b),
+            CollisionEvent::Stopped(..) => continue,
+        };
+
+        // rapier doesn't order the pair
+        let hit = if projectiles.contains(a) && targets.contains(b) {
+            Some((a, b))
+        } else if projectiles.contains(b) && targets.contains(a) {
+            Some((b, a))
+        } else {
+            None
+        };
+
+        if let Some((projectile, target)) = hit {
+            projectile_hits.send(ProjectileHit { projectile, target });
+        }
+    }
+}
+
+fn resolve_projectile_hits(
+    mut commands: Commands,
+    mut projectile_hits: EventReader<ProjectileHit>,
+    mut projectiles: Query<(&mut Projectile, Option<&Splash>), Without<Detonate>>,
+    targets: Query<(&Health, Option<&Defense>), With<Target>>,
+    mut affected: Query<&mut StatusEffects>,
+    mut damage_requests: EventWriter<DamageRequest>,
+    mut stats: ResMut<ProjectileStats>,
+) {
+    for hit in projectile_hits.iter() {
+        let (mut projectile, splash) = match projectiles.get_mut(hit.projectile) {
+            Ok(projectile) => projectile,
+            Err(_) => continue,
+        };
+
+        // spent projectiles can't hit anything else, even within the same frame
+        if !projectile.can_hit(hit.target) {
+            continue;
+        }
+
+        // don't waste the shot on something that is already dead
+        let defense = match targets.get(hit.target) {
+            Ok((health, defense)) if !health.is_dead() => defense,
+            _ => continue,
+        };
+
+        // splash projectiles deal their damage around the impact instead
+        if splash.is_some() {
+            projectile.record_hit(hit.target);
+            commands.entity(hit.projectile).insert(Detonate);
+            continue;
+        }
+
+        if projectile.hit.is_empty() {
+            stats.hit += 1;
+        }
+
+        let damage = calculate_damage(projectile.damage as f32, projectile.damage_type, defense);
+        damage_requests.send(DamageRequest {
+            target: hit.target,
+            amount: damage.round() as i32,
+            source: projectile.origin,
+        });
+
+        if let Ok(mut status_effects) = affected.get_mut(hit.target) {
+            apply_effects(&mut status_effects, &projectile);
+        }
+
+        if projectile.record_hit(hit.target) {
+            commands.entity(hit.projectile).insert(Despawn);
+        }
+    }
+}
+
+fn move_ballistic_projectiles(
+    mut commands: Commands,
+    mut projectiles: Query<(Entity, &mut Ballistic, &mut Transform), Without<Detonate>>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_seconds();
+
+    for (entity, mut ballistic, mut transform) in &mut projectiles {
+        // semi-implicit euler, plenty for a short flight
+        ballistic.velocity.y -= ballistic.gravity * delta;
+        transform.translation += ballistic.velocity * delta;
+
+        // face along the arc
+        let translation = transform.translation;
+        transform.look_at(translation + ballistic.velocity, Vec3::Y);
+
+        if ballistic.velocity.y < 0.0 && transform.translation.y <= ballistic.ground {
+            transform.translation.y = ballistic.ground;
+            commands.entity(entity).insert(Detonate);
+        }
+    }
+}
+
+fn splash_detonation(
     mut commands: Commands,
-    projectile_query: Query<(Entity, &Projectile)>,
-    mut colliding_entities_query: Query<(&mut Health, &CollidingEntities), With<Target>>,
+    projectiles: Query<(Entity, &Projectile, Option<&Splash>, &GlobalTransform), With<Detonate>>,
+    targets: Query<(&Health, &GlobalTransform, Option<&Defense>), With<Target>>,
+    mut affected: Query<&mut StatusEffects>,
+    mut damage_requests: EventWriter<DamageRequest>,
+    mut stats: ResMut<ProjectileStats>,
+    rapier_context: Res<RapierContext>,
 ) {
-    for (mut health, colliding_entities) in colliding_entities_query.iter_mut() {
-        for (projectile_entity, projectile) in projectile_query.iter() {
-            if colliding_entities.contains(projectile_entity) {
-                commands.entity(projectile_entity).insert(Despawn);
-                health.value -= projectile.damage;
+    for (entity, projectile, splash, projectile_transform) in &projectiles {
+        commands.entity(entity).insert(Despawn);
+
+        // without a splash there is nothing to damage once the projectile has landed
+        let splash = match splash {
+            Some(splash) => splash,
+            None => continue,
+        };
+
+        let center = projectile_transform.translation();
+
+        // every target whose collider overlaps the blast
+        let mut caught = Vec::new();
+        let is_target = |entity| targets.contains(entity);
+        rapier_context.intersections_with_shape(
+            center,
+            Quat::IDENTITY,
+            &Collider::ball(splash.radius),
+            QueryFilter::default().predicate(&is_target),
+            |entity| {
+                caught.push(entity);
+                true
+            },
+        );
+
+        let mut hit = false;
+        for target in caught {
+            let (target_transform, defense) = match targets.get(target) {
+                Ok((health, target_transform, defense)) if !health.is_dead() => {
+                    (target_transform, defense)
+                }
+                _ => continue,
+            };
+
+            let distance = target_transform.translation().distance(center);
+            let damage = calculate_damage(
+                splash.damage_at(projectile.damage as f32, distance),
+                projectile.damage_type,
+                defense,
+            );
+            damage_requests.send(DamageRequest {
+                target,
+                amount: damage.round() as i32,
+                source: projectile.origin,
+            });
+            hit = true;
+
+            if let Ok(mut status_effects) = affected.get_mut(target) {
+                apply_effects(&mut status_effects, projectile);
             }
         }
+
+        if hit {
+            stats.hit += 1;
+        }
+    }
+}
+
+fn apply_effects(status_effects: &mut StatusEffects, projectile: &Projectile) {
+    for effect in &projectile.effects {
+        status_effects.apply(effect, projectile.origin);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn projectile_without_pierce_is_spent_on_its_first_hit() {
+        let mut projectile = Projectile::default();
+        let target = Entity::from_raw(1);
+
+        assert!(projectile.can_hit(target));
+        assert!(projectile.record_hit(target));
+        assert!(!projectile.can_hit(Entity::from_raw(2)));
+    }
+
+    #[test]
+    fn pierce_counts_extra_targets_each_hit_once() {
+        let mut projectile = Projectile {
+            pierce: 2,
+            ..default()
+        };
+        let targets = [1, 2, 3, 4].map(Entity::from_raw);
+
+        assert!(!projectile.record_hit(targets[0]));
+        // the same target can't be hit twice, even with pierce left
+        assert!(!projectile.can_hit(targets[0]));
+
+        assert!(projectile.can_hit(targets[1]));
+        assert!(!projectile.record_hit(targets[1]));
+
+        // the third target uses up the last of the pierce
+        assert!(projectile.can_hit(targets[2]));
+        assert!(projectile.record_hit(targets[2]));
+        assert!(!projectile.can_hit(targets[3]));
     }
 }
@@ -0,0 +1,170 @@
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticId, Diagnostics},
+    prelude::*,
+    utils::HashMap,
+};
+
+use crate::{
+    common::{Despawn, Target},
+    state::GameState,
+};
+
+pub struct SpatialPlugin;
+
+impl Plugin for SpatialPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TargetGrid::new(TARGET_GRID_CELL_SIZE))
+            .add_startup_system(setup_spatial_diagnostics)
+            // systems querying the grid run `.after(rebuild_target_grid)`, so every tower
+            // queries the same snapshot
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(rebuild_target_grid),
+            );
+    }
+}
+
+/// Roughly a tower's range, so most range queries only touch a handful of cells
+pub const TARGET_GRID_CELL_SIZE: f32 = 4.0;
+
+/// Targets in range handed to towers this frame, compare against towers x targets to see
+/// what the grid saves over checking every target
+pub const TARGET_CANDIDATES: DiagnosticId =
+    DiagnosticId::from_u128(110876393826738496236105390287531620811);
+
+/// Uniform grid over the ground (XZ) plane bucketing every `Target` by position
+#[derive(Resource)]
+pub struct TargetGrid {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<(Entity, Vec3)>>,
+}
+
+impl TargetGrid {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::default(),
+        }
+    }
+
+    fn cell(&self, position: Vec3) -> IVec2 {
+        IVec2::new(
+            (position.x / self.cell_size).floor() as i32,
+            (position.z / self.cell_size).floor() as i32,
+        )
+    }
+
+    pub fn clear(&mut self) {
+        // keep the allocations around, the same cells are usually occupied next frame
+        for entities in self.cells.values_mut() {
+            entities.clear();
+        }
+    }
+
+    pub fn insert(&mut self, entity: Entity, position: Vec3) {
+        let cell = self.cell(position);
+        self.cells.entry(cell).or_default().push((entity, position));
+    }
+
+    /// Targets within `radius` of `center` along with their positions
+    pub fn query_range(&self, center: Vec3, radius: f32) -> Vec<(Entity, Vec3)> {
+        let min = self.cell(center - Vec3::splat(radius));
+        let max = self.cell(center + Vec3::splat(radius));
+
+        let mut in_range = Vec::new();
+        for x in min.x..=max.x {
+            for z in min.y..=max.y {
+                if let Some(entities) = self.cells.get(&IVec2::new(x, z)) {
+                    in_range.extend(
+                        entities
+                            .iter()
+                            .filter(|(_, position)| position.distance(center) <= radius),
+                    );
+                }
+            }
+        }
+
+        in_range
+    }
+}
+
+/// Targets that haven't been killed or leaked yet
+type LiveTarget = (With<Target>, Without<Despawn>);
+
+pub fn rebuild_target_grid(
+    mut grid: ResMut<TargetGrid>,
+    targets: Query<(Entity, &GlobalTransform), LiveTarget>,
+) {
+    grid.clear();
+
+    for (entity, transform) in &targets {
+        grid.insert(entity, transform.translation());
+    }
+}
+
+fn setup_spatial_diagnostics(mut diagnostics: ResMut<Diagnostics>) {
+    diagnostics.add(Diagnostic::new(TARGET_CANDIDATES, "target_candidates", 20));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic scatter of positions over both sides of the origin
+    fn scattered_positions(count: u32) -> Vec<(Entity, Vec3)> {
+        let mut seed: u32 = 0x9e37_79b9;
+        let mut next = move || {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (seed >> 8) as f32 / (1 << 24) as f32 * 20.0 - 10.0
+        };
+
+        (0..count)
+            .map(|index| (Entity::from_raw(index), Vec3::new(next(), 0.5, next())))
+            .collect()
+    }
+
+    fn sorted(mut entities: Vec<Entity>) -> Vec<Entity> {
+        entities.sort();
+        entities
+    }
+
+    #[test]
+    fn query_range_matches_a_brute_force_scan() {
+        let targets = scattered_positions(500);
+        let mut grid = TargetGrid::new(TARGET_GRID_CELL_SIZE);
+        for (entity, position) in &targets {
+            grid.insert(*entity, *position);
+        }
+
+        // centers on cell corners, inside cells and in negative coordinates, with ranges
+        // smaller than, equal to and spanning several cells
+        let centers = [
+            Vec3::ZERO,
+            Vec3::new(2.0, 0.5, -2.0),
+            Vec3::new(4.0, 0.5, -4.0),
+            Vec3::new(-3.3, 0.5, -7.9),
+            Vec3::new(-0.1, 0.5, 0.1),
+            Vec3::new(7.5, 0.5, 4.2),
+        ];
+
+        for center in centers {
+            for radius in [0.5, 2.0, 3.7, 8.0] {
+                let expected = targets
+                    .iter()
+                    .filter(|(_, position)| position.distance(center) <= radius)
+                    .map(|(entity, _)| *entity)
+                    .collect();
+                let found = grid
+                    .query_range(center, radius)
+                    .into_iter()
+                    .map(|(entity, _)| entity)
+                    .collect();
+
+                assert_eq!(
+                    sorted(found),
+                    sorted(expected),
+                    "range {radius} around {center}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+use crate::{common::Despawn, input::Action};
+
+pub struct StatePlugin;
+
+impl Plugin for StatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state(GameState::Loading)
+            .add_system(toggle_pause)
+            // Quitting from the pause menu goes straight here, skipping Victory/Defeat
+            .add_system_set(SystemSet::on_enter(GameState::MainMenu).with_system(cleanup_session))
+            .add_system_set(SystemSet::on_exit(GameState::Victory).with_system(cleanup_session))
+            .add_system_set(SystemSet::on_exit(GameState::Defeat).with_system(cleanup_session));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameState {
+    Loading,
+    MainMenu,
+    Playing,
+    Paused,
+    Victory,
+    Defeat,
+}
+
+/// Marks entities that belong to a single play session (level, towers, enemies, hud),
+/// these are despawned once the session is over so a level can be restarted
+#[derive(Component, Default)]
+pub struct SessionEntity;
+
+fn toggle_pause(action_state: Res<ActionState<Action>>, mut state: ResMut<State<GameState>>) {
+    if !action_state.just_pressed(Action::TogglePause) {
+        return;
+    }
+
+    let result = match state.current() {
+        GameState::Playing => state.push(GameState::Paused),
+        GameState::Paused => state.pop(),
+        _ => return,
+    };
+
+    // another transition was queued this frame, e.g. a menu click, let that one win
+    if let Err(err) = result {
+        warn!("Pause toggle failed: {:?}", err);
+    }
+}
+
+fn cleanup_session(mut commands: Commands, entities: Query<Entity, With<SessionEntity>>) {
+    for entity in entities.iter() {
+        commands.entity(entity).insert(Despawn);
+    }
+}
@@ -0,0 +1,318 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    common::{apply_health_requests, DamageRequest, Health},
+    damage::{calculate_damage, DamageType, Defense},
+    data::{ensure_non_negative, ensure_positive},
+    navigation::NavAgent,
+    state::GameState,
+};
+
+pub struct StatusPlugin;
+
+impl Plugin for StatusPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<StatusEffects>().add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(tick_status_effects.before(apply_health_requests))
+                .with_system(apply_status_move_speed.after(tick_status_effects)),
+        );
+    }
+}
+
+/// Slowest a stack of slows can make something, as a share of its base speed
+const MIN_SLOW_FACTOR: f32 = 0.2;
+
+#[derive(Reflect, FromReflect, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum StatusEffectKind {
+    /// Multiplies move speed by `factor`
+    Slow {
+        factor: f32,
+    },
+    Burn {
+        damage_per_second: f32,
+    },
+    Poison {
+        damage_per_second: f32,
+    },
+    /// No movement at all while it lasts
+    Stun,
+}
+
+impl Default for StatusEffectKind {
+    fn default() -> Self {
+        StatusEffectKind::Slow { factor: 1.0 }
+    }
+}
+
+impl StatusEffectKind {
+    /// Damage per second and its type, for effects that deal damage over time
+    fn damage_over_time(&self) -> Option<(f32, DamageType)> {
+        match self {
+            StatusEffectKind::Burn { damage_per_second } => {
+                Some((*damage_per_second, DamageType::Fire))
+            }
+            StatusEffectKind::Poison { damage_per_second } => {
+                Some((*damage_per_second, DamageType::Poison))
+            }
+            StatusEffectKind::Slow { .. } | StatusEffectKind::Stun => None,
+        }
+    }
+}
+
+/// An effect a projectile applies on hit
+#[derive(Reflect, FromReflect, Deserialize, Clone, Copy, Default, Debug)]
+pub struct StatusEffect {
+    pub kind: StatusEffectKind,
+    /// Seconds
+    pub duration: f32,
+    /// How many of this effect can be active on one target at once, applying it again
+    /// beyond that refreshes the oldest instead
+    #[serde(default = "default_max_stacks")]
+    pub max_stacks: u32,
+}
+
+fn default_max_stacks() -> u32 {
+    1
+}
+
+impl StatusEffect {
+    /// Checked when definitions load, the effect is only built on the first hit mid-game
+    pub fn validate(&self, owner: &str) -> Result<(), String> {
+        ensure_non_negative(owner, "effect duration", self.duration)?;
+
+        match self.kind {
+            StatusEffectKind::Slow { factor } => {
+                if !(0.0..=1.0).contains(&factor) {
+                    return Err(format!(
+                        "{owner}: slow factor must be between 0 and 1, got {factor}"
+                    ));
+                }
+            }
+            StatusEffectKind::Burn { damage_per_second }
+            | StatusEffectKind::Poison { damage_per_second } => {
+                ensure_positive(owner, "effect damage_per_second", damage_per_second)?;
+            }
+            StatusEffectKind::Stun => {}
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Reflect, FromReflect, Default)]
+pub struct ActiveStatusEffect {
+    pub kind: StatusEffectKind,
+    pub timer: Timer,
+    /// Whatever applied the effect, credited with any damage it deals
+    pub source: Option<Entity>,
+    /// Damage over time that hasn't added up to a whole point yet
+    pub pending_damage: f32,
+}
+
+/// Effects currently active on an entity, along with the move speed they are applied to
+#[derive(Reflect, Component, Default)]
+#[reflect(Component)]
+pub struct StatusEffects {
+    pub base_move_speed: f32,
+    pub active: Vec<ActiveStatusEffect>,
+}
+
+impl StatusEffects {
+    pub fn new(base_move_speed: f32) -> Self {
+        Self {
+            base_move_speed,
+            active: Vec::new(),
+        }
+    }
+
+    pub fn apply(&mut self, effect: &StatusEffect, source: Option<Entity>) {
+        let timer = Timer::from_seconds(effect.duration, TimerMode::Once);
+
+        let stacks = self
+            .active
+            .iter()
+            .filter(|active| active.kind == effect.kind)
+            .count();
+
+        // at the stack limit, the oldest instance (the one with the least time left) starts over
+        if stacks >= effect.max_stacks.max(1) as usize {
+            if let Some(oldest) = self
+                .active
+                .iter_mut()
+                .filter(|active| active.kind == effect.kind)
+                .min_by(|a, b| a.timer.remaining().cmp(&b.timer.remaining()))
+            {
+                oldest.timer = timer;
+                oldest.source = source;
+            }
+            return;
+        }
+
+        self.active.push(ActiveStatusEffect {
+            kind: effect.kind,
+            timer,
+            source,
+            pending_damage: 0.0,
+        });
+    }
+
+    pub fn is_stunned(&self) -> bool {
+        self.active
+            .iter()
+            .any(|active| matches!(active.kind, StatusEffectKind::Stun))
+    }
+
+    /// Combined slow of every active slow, stacks multiply
+    pub fn slow_factor(&self) -> f32 {
+        self.active
+            .iter()
+            .filter_map(|active| match active.kind {
+                StatusEffectKind::Slow { factor } => Some(factor.clamp(0.0, 1.0)),
+                _ => None,
+            })
+            .product::<f32>()
+            .max(MIN_SLOW_FACTOR)
+    }
+
+    pub fn move_speed(&self) -> f32 {
+        if self.is_stunned() {
+            return 0.0;
+        }
+
+        self.base_move_speed * self.slow_factor()
+    }
+}
+
+fn tick_status_effects(
+    mut affected: Query<(Entity, &mut StatusEffects, &Health, Option<&Defense>)>,
+    mut damage_requests: EventWriter<DamageRequest>,
+    time: Res<Time>,
+) {
+    for (entity, mut status_effects, health, defense) in &mut affected {
+        for active in &mut status_effects.active {
+            active.timer.tick(time.delta());
+
+            // damage over time, dealt in whole points as it accumulates
+            if let Some((damage_per_second, damage_type)) = active.kind.damage_over_time() {
+                active.pending_damage += calculate_damage(
+                    damage_per_second * time.delta_seconds(),
+                    damage_type,
+                    defense,
+                );
+            }
+
+            let damage = active.pending_damage.floor();
+            if damage < 1.0 || health.is_dead() {
+                continue;
+            }
+
+            active.pending_damage -= damage;
+            damage_requests.send(DamageRequest {
+                target: entity,
+                amount: damage as i32,
+                source: active.source,
+            });
+        }
+
+        status_effects
+            .active
+            .retain(|active| !active.timer.finished());
+    }
+}
+
+/// Keeps move speed in line with whatever slows and stuns are active, back to the base speed
+/// once they have all expired
+fn apply_status_move_speed(mut agents: Query<(&StatusEffects, &mut NavAgent)>) {
+    for (status_effects, mut nav_agent) in &mut agents {
+        let move_speed = status_effects.move_speed();
+        if nav_agent.move_speed != move_speed {
+            nav_agent.move_speed = move_speed;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn slow(factor: f32, max_stacks: u32) -> StatusEffect {
+        StatusEffect {
+            kind: StatusEffectKind::Slow { factor },
+            duration: 2.0,
+            max_stacks,
+        }
+    }
+
+    #[test]
+    fn applying_past_the_stack_limit_refreshes_the_oldest() {
+        let mut effects = StatusEffects::new(1.0);
+        let effect = slow(0.5, 2);
+
+        effects.apply(&effect, None);
+        effects.active[0].timer.tick(Duration::from_secs_f32(1.5));
+        effects.apply(&effect, None);
+        effects.active[1].timer.tick(Duration::from_secs_f32(0.5));
+
+        let source = Entity::from_raw(7);
+        effects.apply(&effect, Some(source));
+
+        // still two stacks, the one closest to running out starts over
+        assert_eq!(effects.active.len(), 2);
+        assert_eq!(effects.active[0].timer.elapsed(), Duration::ZERO);
+        assert_eq!(effects.active[0].source, Some(source));
+        assert_eq!(
+            effects.active[1].timer.elapsed(),
+            Duration::from_secs_f32(0.5)
+        );
+    }
+
+    #[test]
+    fn different_kinds_stack_separately() {
+        let mut effects = StatusEffects::new(1.0);
+
+        effects.apply(&slow(0.5, 1), None);
+        effects.apply(&slow(0.8, 1), None);
+        effects.apply(
+            &StatusEffect {
+                kind: StatusEffectKind::Stun,
+                duration: 1.0,
+                max_stacks: 1,
+            },
+            None,
+        );
+
+        assert_eq!(effects.active.len(), 3);
+    }
+
+    #[test]
+    fn slows_multiply_down_to_the_minimum_factor() {
+        let mut effects = StatusEffects::new(2.0);
+
+        effects.apply(&slow(0.5, 3), None);
+        effects.apply(&slow(0.8, 3), None);
+        assert!((effects.slow_factor() - 0.4).abs() < 1e-6);
+        assert!((effects.move_speed() - 0.8).abs() < 1e-6);
+
+        effects.apply(&slow(0.1, 3), None);
+        assert_eq!(effects.slow_factor(), MIN_SLOW_FACTOR);
+    }
+
+    #[test]
+    fn stunned_targets_dont_move() {
+        let mut effects = StatusEffects::new(2.0);
+        effects.apply(
+            &StatusEffect {
+                kind: StatusEffectKind::Stun,
+                duration: 1.0,
+                max_stacks: 1,
+            },
+            None,
+        );
+
+        assert_eq!(effects.move_speed(), 0.0);
+    }
+}
@@ -0,0 +1,19 @@
+mod builder;
+mod economy;
+mod enemy;
+mod game;
+mod range_indicator;
+mod selection;
+mod tower;
+mod ui;
+mod world;
+
+pub use builder::*;
+pub use economy::*;
+pub use enemy::*;
+pub use game::*;
+pub use range_indicator::*;
+pub use selection::*;
+pub use tower::*;
+pub use ui::*;
+pub use world::*;
@@ -1,21 +1,8 @@
-mod builder;
-mod enemy;
-mod game;
-mod tower;
-mod ui;
-mod world;
-
-pub use builder::*;
-pub use enemy::*;
-pub use game::*;
-pub use tower::*;
-pub use ui::*;
-pub use world::*;
+use bevy_tower_defense::*;
 
 use bevy::{
     diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
     prelude::*,
-    utils::FloatOrd,
 };
 use bevy_inspector_egui::WorldInspectorPlugin;
 use bevy_mod_picking::DefaultPickingPlugins;
@@ -33,16 +20,24 @@ fn main() {
             gravity: Vec3::ZERO,
             ..default()
         })
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
-            window: WindowDescriptor {
-                width: WIDTH,
-                height: HEIGHT,
-                title: "Bevy Tower Defense 0.1".to_string(),
-                resizable: false,
-                ..default()
-            },
-            ..default()
-        }))
+        .add_plugins(
+            DefaultPlugins
+                .set(WindowPlugin {
+                    window: WindowDescriptor {
+                        width: WIDTH,
+                        height: HEIGHT,
+                        title: "Bevy Tower Defense 0.1".to_string(),
+                        resizable: false,
+                        ..default()
+                    },
+                    ..default()
+                })
+                // Hot reload data files (tower definitions etc.) while the game is running
+                .set(AssetPlugin {
+                    watch_for_changes: true,
+                    ..default()
+                }),
+        )
         // Inspector Plugin
         .add_plugin(WorldInspectorPlugin::new())
         // Mod Picking
@@ -58,10 +53,13 @@ fn main() {
         .add_plugins(DefaultWorldPlugins)
         .add_plugin(TowerPlugin)
         .add_plugin(EnemyPlugin)
+        .add_plugin(EconomyPlugin)
         .add_plugin(BuilderPlugin)
+        .add_plugin(SelectionPlugin)
+        .add_plugin(RangeIndicatorPlugin)
         .add_plugin(UiPlugin)
         // Debug Systems
         .add_plugin(LogDiagnosticsPlugin::default())
-        .add_plugin(FrameTimeDiagnosticsPlugin::default())
+        .add_plugin(FrameTimeDiagnosticsPlugin)
         .run();
 }
@@ -0,0 +1,170 @@
+use std::f32::consts::TAU;
+
+use bevy::{
+    pbr::NotShadowCaster,
+    prelude::*,
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+    utils::HashSet,
+};
+use bevy_mod_picking::Hover;
+
+use crate::{state::GameState, SelectedTower, Tower, TowerPart, TOWER_HEIGHT_OFFSET};
+
+pub struct RangeIndicatorPlugin;
+
+impl Plugin for RangeIndicatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<RangeIndicator>()
+            .add_startup_system_to_stage(StartupStage::PreStartup, load_range_indicator_assets)
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(attach_range_indicators)
+                    .with_system(update_range_indicators),
+            );
+    }
+}
+
+/// Share of the ring's radius taken up by the ring itself
+const RING_WIDTH: f32 = 0.04;
+const RING_SEGMENTS: u32 = 64;
+/// Lift off the ground so the ring doesn't z-fight with the tiles
+const RING_HEIGHT: f32 = 0.02;
+
+/*
+   RANGE INDICATOR SPECIFIC ASSETS (ON STARTUP)
+*/
+#[derive(Resource)]
+pub struct RangeIndicatorAssets {
+    pub ring: Handle<Mesh>,
+    pub ring_color: Handle<StandardMaterial>,
+}
+
+fn load_range_indicator_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(RangeIndicatorAssets {
+        ring: meshes.add(ring_mesh(1.0 - RING_WIDTH, 1.0, RING_SEGMENTS)),
+        ring_color: materials.add(StandardMaterial {
+            base_color: Color::rgba(1.0, 1.0, 1.0, 0.6),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        }),
+    });
+}
+
+/// Flat ring on the XZ plane facing up, between `inner_radius` and `outer_radius`
+fn ring_mesh(inner_radius: f32, outer_radius: f32, segments: u32) -> Mesh {
+    let mut positions = Vec::with_capacity(segments as usize * 2);
+    let mut uvs = Vec::with_capacity(segments as usize * 2);
+    let mut indices = Vec::with_capacity(segments as usize * 6);
+
+    for segment in 0..segments {
+        let angle = segment as f32 / segments as f32 * TAU;
+        let (sin, cos) = angle.sin_cos();
+        let u = segment as f32 / segments as f32;
+
+        positions.push([cos * inner_radius, 0.0, sin * inner_radius]);
+        positions.push([cos * outer_radius, 0.0, sin * outer_radius]);
+        uvs.push([u, 0.0]);
+        uvs.push([u, 1.0]);
+
+        // two triangles joining this segment to the next, wrapping around at the end
+        let inner = segment * 2;
+        let outer = inner + 1;
+        let next_inner = (inner + 2) % (segments * 2);
+        let next_outer = next_inner + 1;
+        indices.extend([inner, next_inner, outer, outer, next_inner, next_outer]);
+    }
+
+    let normals = vec![[0.0, 1.0, 0.0]; positions.len()];
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+/*
+    RANGE INDICATOR
+*/
+
+/// Ground ring showing the range of the tower (or tower ghost) it is parented to
+#[derive(Reflect, Component, Default)]
+#[reflect(Component)]
+pub struct RangeIndicator;
+
+#[derive(Bundle)]
+pub struct RangeIndicatorBundle {
+    range_indicator: RangeIndicator,
+    pbr_bundle: PbrBundle,
+    not_shadow_caster: NotShadowCaster,
+}
+
+impl RangeIndicatorBundle {
+    /// A ring of radius `range`, `height` is the ground level relative to the parent
+    pub fn new(assets: &RangeIndicatorAssets, range: f32, height: f32) -> Self {
+        Self {
+            range_indicator: RangeIndicator,
+            pbr_bundle: PbrBundle {
+                mesh: assets.ring.clone(),
+                material: assets.ring_color.clone(),
+                transform: Transform::from_xyz(0.0, height + RING_HEIGHT, 0.0)
+                    .with_scale(Vec3::new(range, 1.0, range)),
+                ..default()
+            },
+            not_shadow_caster: NotShadowCaster,
+        }
+    }
+
+    pub fn hidden(mut self) -> Self {
+        self.pbr_bundle.visibility = Visibility::INVISIBLE;
+        self
+    }
+}
+
+fn attach_range_indicators(
+    mut commands: Commands,
+    towers: Query<(Entity, &Tower), Added<Tower>>,
+    assets: Res<RangeIndicatorAssets>,
+) {
+    for (entity, tower) in &towers {
+        // towers sit slightly above their tile, put the ring back down on it
+        commands.entity(entity).with_children(|cmd| {
+            cmd.spawn(
+                RangeIndicatorBundle::new(&assets, tower.range, -TOWER_HEIGHT_OFFSET).hidden(),
+            );
+        });
+    }
+}
+
+/// Shows a tower's ring while it is hovered or selected, keeping it in step with upgrades
+fn update_range_indicators(
+    mut indicators: Query<(&Parent, &mut Transform, &mut Visibility), With<RangeIndicator>>,
+    towers: Query<&Tower>,
+    parts: Query<(&TowerPart, &Hover)>,
+    selected: Res<SelectedTower>,
+) {
+    let hovered: HashSet<Entity> = parts
+        .iter()
+        .filter(|(_, hover)| hover.hovered())
+        .map(|(part, _)| part.tower)
+        .collect();
+
+    for (parent, mut transform, mut visibility) in &mut indicators {
+        let tower_entity = parent.get();
+
+        // indicators under anything but a tower (the builder ghost) are left alone
+        let tower = match towers.get(tower_entity) {
+            Ok(tower) => tower,
+            Err(_) => continue,
+        };
+
+        transform.scale = Vec3::new(tower.range, 1.0, tower.range);
+        visibility.is_visible = hovered.contains(&tower_entity) || selected.0 == Some(tower_entity);
+    }
+}
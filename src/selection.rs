@@ -0,0 +1,56 @@
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+use crate::{common::Despawn, input::Action, state::GameState, Tower, TowerPart};
+
+pub struct SelectionPlugin;
+
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SelectedTower>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_selection))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(select_tower)
+                    .with_system(clear_selection)
+                    .with_system(drop_removed_selection),
+            );
+    }
+}
+
+/// The tower the info panel is showing, if any
+#[derive(Resource, Default)]
+pub struct SelectedTower(pub Option<Entity>);
+
+fn reset_selection(mut selected: ResMut<SelectedTower>) {
+    *selected = SelectedTower::default();
+}
+
+fn select_tower(
+    interaction: Query<(&Interaction, &TowerPart), Changed<Interaction>>,
+    mut selected: ResMut<SelectedTower>,
+) {
+    for (interaction, part) in &interaction {
+        if matches!(interaction, Interaction::Clicked) {
+            selected.0 = Some(part.tower);
+        }
+    }
+}
+
+fn clear_selection(action_state: Res<ActionState<Action>>, mut selected: ResMut<SelectedTower>) {
+    if action_state.just_pressed(Action::ClearSelection) && selected.0.is_some() {
+        selected.0 = None;
+    }
+}
+
+/// Sold (or otherwise removed) towers can't stay selected
+fn drop_removed_selection(
+    mut selected: ResMut<SelectedTower>,
+    towers: Query<(), (With<Tower>, Without<Despawn>)>,
+) {
+    if let Some(tower) = selected.0 {
+        if !towers.contains(tower) {
+            selected.0 = None;
+        }
+    }
+}
@@ -1,13 +1,33 @@
 use crate::{
-    assets::GameAssets,
-    common::{Lifetime, Target},
-    navigation::NavAgent,
+    common::{DamageStats, Despawn, Health, Lifetime, Target},
+    damage::DamageType,
+    data::{ensure_non_negative, ensure_positive, RonAssetLoader, Validate},
+    hooks::{SceneHook, SceneHooked},
+    intercept::{ballistic_intercept, ballistic_launch, route_intercept},
+    navigation::{NavAgent, WAYPOINT_OFFSET},
     physics::PhysicsBundle,
-    projectile::Projectile,
+    projectile::{Ballistic, Homing, Projectile, ProjectileStats, Splash, TargetLost},
+    spatial::{rebuild_target_grid, TargetGrid, TARGET_CANDIDATES},
+    state::{GameState, SessionEntity},
+    status::StatusEffect,
     *,
 };
 
-use bevy::prelude::*;
+use std::{
+    f32::consts::{PI, TAU},
+    time::Duration,
+};
+
+use bevy::{
+    asset::HandleId,
+    diagnostic::Diagnostics,
+    prelude::*,
+    reflect::TypeUuid,
+    ui::FocusPolicy,
+    utils::{FloatOrd, HashMap},
+};
+use bevy_mod_picking::{Hover, PickableMesh};
+use serde::Deserialize;
 
 pub struct TowerPlugin;
 
@@ -15,7 +35,22 @@ impl Plugin for TowerPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<Tower>()
             .register_type::<TowerType>()
-            .add_system(tower_shooting);
+            .register_type::<TargetingMode>()
+            .register_type::<TowerPart>()
+            .register_type::<TurretHead>()
+            .add_asset::<TowerDefinitions>()
+            .add_asset_loader(RonAssetLoader::<TowerDefinitions>::new(&["towers.ron"]))
+            .add_event::<UpgradeTower>()
+            .add_event::<SellTower>()
+            .add_system(build_tower_registry)
+            .add_system(warn_missing_turret_heads)
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(tower_shooting.after(rebuild_target_grid))
+                    .with_system(rotate_turret_heads.after(tower_shooting))
+                    .with_system(upgrade_towers)
+                    .with_system(sell_towers),
+            );
     }
 }
 
@@ -24,10 +59,113 @@ impl Plugin for TowerPlugin {
 pub struct Tower {
     pub shooting_timer: Timer,
     pub projectile_offset: Vec3,
+    pub projectile_speed: f32,
+    pub projectile_scene: Handle<Scene>,
     pub range: f32,
+    pub damage: i32,
+    pub damage_type: DamageType,
+    pub firing_model: FiringModel,
+    pub homing: Option<HomingConfig>,
+    pub splash: Option<Splash>,
+    /// Extra targets each shot passes through
+    pub pierce: u32,
+    /// Status effects each shot applies to what it hits
+    pub effects: Vec<StatusEffect>,
+    /// Upgrade level, 0 is the tower as built
+    pub level: usize,
+    /// Gold spent on building and upgrading, the basis for the sell refund
+    pub invested: u32,
+    /// Shots left to fire in the current volley
+    pub shots_remaining: u32,
+    pub burst_timer: Timer,
+    /// Heading of the turret around Y in radians, 0 is the model's rest facing (+Z)
+    pub turret_yaw: f32,
+    /// Radians per second
+    pub turn_rate: f32,
+    /// How far off (in radians) the turret may be from its shot and still fire
+    pub aim_tolerance: f32,
+    /// Aim where the target is going to be rather than where it is
+    pub lead_targets: bool,
+}
+
+/// Which target in range a tower prefers
+#[derive(Reflect, Component, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[reflect(Component)]
+pub enum TargetingMode {
+    /// Furthest along the route
+    #[default]
+    First,
+    /// Least far along the route
+    Last,
+    /// Most health remaining
+    Strongest,
+    /// Least health remaining
+    Weakest,
+    /// Closest to the tower
+    Closest,
+}
+
+impl TargetingMode {
+    /// The next mode in the cycle, for switching modes at runtime
+    pub fn next(&self) -> Self {
+        match self {
+            TargetingMode::First => TargetingMode::Last,
+            TargetingMode::Last => TargetingMode::Strongest,
+            TargetingMode::Strongest => TargetingMode::Weakest,
+            TargetingMode::Weakest => TargetingMode::Closest,
+            TargetingMode::Closest => TargetingMode::First,
+        }
+    }
+
+    /// Ranks a candidate target, the lowest score is the preferred target
+    fn score(&self, distance: f32, remaining_distance: f32, health: i32) -> FloatOrd {
+        FloatOrd(match self {
+            TargetingMode::First => remaining_distance,
+            TargetingMode::Last => -remaining_distance,
+            TargetingMode::Strongest => -(health as f32),
+            TargetingMode::Weakest => health as f32,
+            TargetingMode::Closest => distance,
+        })
+    }
+}
+
+/// How a tower delivers its damage
+#[derive(Reflect, FromReflect, Deserialize, Clone, Copy, Default, Debug)]
+pub enum FiringModel {
+    /// A single straight shot at one target
+    #[default]
+    Single,
+    /// A shot lobbed over an arc under `gravity`, detonating where it lands
+    Lobbed { arc_height: f32, gravity: f32 },
+    /// A burst of shots per volley, each aimed at the current target
+    Rapid { burst: u32, burst_interval: f32 },
+}
+
+/// Shots that steer after their target instead of flying straight
+#[derive(Reflect, FromReflect, Deserialize, Clone, Copy, Default, Debug)]
+pub struct HomingConfig {
+    /// Degrees per second
+    pub turn_rate: f32,
+    pub on_target_lost: TargetLost,
+}
+
+impl FiringModel {
+    fn shots_per_volley(&self) -> u32 {
+        match self {
+            FiringModel::Rapid { burst, .. } => *burst,
+            _ => 1,
+        }
+    }
+
+    fn shot_interval(&self) -> f32 {
+        match self {
+            FiringModel::Rapid { burst_interval, .. } => *burst_interval,
+            _ => 0.0,
+        }
+    }
 }
 
-#[derive(Reflect, Component, Clone, Copy, Default)]
+#[derive(Reflect, Component, Deserialize, Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
 #[reflect(Component)]
 pub enum TowerType {
     #[default]
@@ -36,121 +174,685 @@ pub enum TowerType {
     Blaster,
 }
 
+/*
+    TOWER DEFINITIONS (LOADED FROM ASSETS)
+*/
+
+/// Designer tunable tower stats, see `assets/data/default.towers.ron`
+#[derive(Deserialize, Clone)]
+pub struct TowerDefinition {
+    pub tower_type: TowerType,
+    pub name: String,
+    pub cost: u32,
+    /// Shots per second
+    pub fire_rate: f32,
+    pub range: f32,
+    pub damage: i32,
+    #[serde(default)]
+    pub damage_type: DamageType,
+    /// Ignored (and can be left out) for lobbed towers, their shots take as long as their arc
+    #[serde(default)]
+    pub projectile_speed: f32,
+    pub projectile_offset: [f32; 3],
+    pub projectile_scene: String,
+    pub firing_model: FiringModel,
+    #[serde(default)]
+    pub homing: Option<HomingConfig>,
+    /// Damage every target around the impact instead of just the one hit
+    #[serde(default)]
+    pub splash: Option<Splash>,
+    /// Extra targets each shot passes through before it is spent
+    #[serde(default)]
+    pub pierce: u32,
+    /// Status effects applied to every target a shot damages
+    #[serde(default)]
+    pub effects: Vec<StatusEffect>,
+    pub scene: String,
+    pub icon: String,
+    /// Name (prefix) of the node in `scene` that turns to face the target
+    #[serde(default)]
+    pub turret_node: Option<String>,
+    /// Degrees per second
+    pub turn_rate: f32,
+    /// Degrees
+    pub aim_tolerance: f32,
+    /// Upgrade levels, applied in order on top of the base stats
+    #[serde(default)]
+    pub upgrades: Vec<TowerUpgrade>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct TowerUpgrade {
+    pub cost: u32,
+    /// Shots per second
+    pub fire_rate: f32,
+    pub range: f32,
+    pub damage: i32,
+    /// Replaces the tower's pierce from this level on
+    #[serde(default)]
+    pub pierce: Option<u32>,
+    /// Optional replacement model for this level
+    #[serde(default)]
+    pub scene: Option<String>,
+}
+
+#[derive(Deserialize, TypeUuid)]
+#[uuid = "6f1b1e0c-8a5e-4b8e-9a63-2f8a4b7f0d11"]
+pub struct TowerDefinitions {
+    /// Share of the gold invested in a tower that is paid back when it is sold
+    pub sell_refund_percent: u32,
+    pub towers: Vec<TowerDefinition>,
+}
+
+impl Validate for TowerDefinitions {
+    fn validate(&self) -> Result<(), String> {
+        if self.sell_refund_percent > 100 {
+            return Err(format!(
+                "sell_refund_percent must be between 0 and 100, got {}",
+                self.sell_refund_percent
+            ));
+        }
+
+        for tower in &self.towers {
+            let name = tower.name.as_str();
+            ensure_positive(name, "fire_rate", tower.fire_rate)?;
+            ensure_positive(name, "range", tower.range)?;
+            ensure_positive(name, "turn_rate", tower.turn_rate)?;
+            ensure_non_negative(name, "aim_tolerance", tower.aim_tolerance)?;
+
+            match tower.firing_model {
+                FiringModel::Single => {
+                    ensure_positive(name, "projectile_speed", tower.projectile_speed)?;
+                }
+                // the launch velocity is solved from these, anything else makes it NaN
+                FiringModel::Lobbed {
+                    arc_height,
+                    gravity,
+                } => {
+                    ensure_non_negative(name, "arc_height", arc_height)?;
+                    ensure_positive(name, "gravity", gravity)?;
+
+                    // a landing shot only damages through its splash
+                    if tower.splash.is_none() {
+                        return Err(format!("{name}: lobbed towers need a splash"));
+                    }
+                }
+                FiringModel::Rapid { burst_interval, .. } => {
+                    ensure_positive(name, "projectile_speed", tower.projectile_speed)?;
+                    ensure_non_negative(name, "burst_interval", burst_interval)?;
+                }
+            }
+
+            if let Some(splash) = &tower.splash {
+                splash.validate(name)?;
+            }
+
+            for effect in &tower.effects {
+                effect.validate(name)?;
+            }
+
+            // checked here, an upgrade is only applied once the player pays for it mid-game
+            for (index, upgrade) in tower.upgrades.iter().enumerate() {
+                let owner = format!("{name} upgrade {}", index + 1);
+                ensure_positive(&owner, "cost", upgrade.cost as f32)?;
+                ensure_positive(&owner, "fire_rate", upgrade.fire_rate)?;
+                ensure_positive(&owner, "range", upgrade.range)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A tower definition along with the handles of the assets it references
+pub struct TowerBlueprint {
+    pub definition: TowerDefinition,
+    pub scene: Handle<Scene>,
+    pub projectile_scene: Handle<Scene>,
+    pub icon: Handle<Image>,
+    /// Scene overrides per upgrade, matching `definition.upgrades`
+    pub upgrade_scenes: Vec<Option<Handle<Scene>>>,
+}
+
+impl TowerBlueprint {
+    /// The upgrade that takes a tower from `level` to the next, if there is one
+    pub fn next_upgrade(&self, level: usize) -> Option<&TowerUpgrade> {
+        self.definition.upgrades.get(level)
+    }
+}
+
+/// Lookup of tower blueprints by type, rebuilt whenever the definitions asset (re)loads
+///
+/// Towers that are already placed keep the stats they were built and upgraded with, reloaded
+/// definitions only apply to towers built or upgraded afterwards
+#[derive(Resource, Default)]
+pub struct TowerRegistry {
+    towers: HashMap<TowerType, TowerBlueprint>,
+    order: Vec<TowerType>,
+}
+
+impl TowerRegistry {
+    /// `None` if the loaded definitions don't include `tower_type`
+    pub fn get(&self, tower_type: TowerType) -> Option<&TowerBlueprint> {
+        self.towers.get(&tower_type)
+    }
+
+    /// Tower types in the order they were defined
+    pub fn tower_types(&self) -> &[TowerType] {
+        &self.order
+    }
+
+    pub fn handles(&self) -> impl Iterator<Item = HandleId> + '_ {
+        self.towers.values().flat_map(|tower| {
+            [
+                tower.scene.id(),
+                tower.projectile_scene.id(),
+                tower.icon.id(),
+            ]
+            .into_iter()
+            .chain(
+                tower
+                    .upgrade_scenes
+                    .iter()
+                    .flatten()
+                    .map(|scene| scene.id()),
+            )
+        })
+    }
+}
+
+fn build_tower_registry(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<TowerDefinitions>>,
+    definitions: Res<Assets<TowerDefinitions>>,
+    asset_server: Res<AssetServer>,
+) {
+    for event in events.iter() {
+        let handle = match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => handle,
+            AssetEvent::Removed { .. } => continue,
+        };
+
+        let definitions = match definitions.get(handle) {
+            Some(definitions) => definitions,
+            None => continue,
+        };
+
+        let mut registry = TowerRegistry::default();
+        for definition in &definitions.towers {
+            registry.order.push(definition.tower_type);
+            registry.towers.insert(
+                definition.tower_type,
+                TowerBlueprint {
+                    definition: definition.clone(),
+                    scene: asset_server.load(definition.scene.as_str()),
+                    projectile_scene: asset_server.load(definition.projectile_scene.as_str()),
+                    icon: asset_server.load(definition.icon.as_str()),
+                    upgrade_scenes: definition
+                        .upgrades
+                        .iter()
+                        .map(|upgrade| {
+                            upgrade
+                                .scene
+                                .as_ref()
+                                .map(|scene| asset_server.load(scene.as_str()))
+                        })
+                        .collect(),
+                },
+            );
+        }
+
+        commands.insert_resource(registry);
+        commands.insert_resource(SellRefund::new(definitions.sell_refund_percent));
+    }
+}
+
+/// Mesh belonging to a tower's scene, pointing back at the tower it is part of
+#[derive(Reflect, Component)]
+#[reflect(Component)]
+pub struct TowerPart {
+    pub tower: Entity,
+}
+
+impl FromWorld for TowerPart {
+    fn from_world(_world: &mut World) -> Self {
+        Self {
+            tower: Entity::from_raw(u32::MAX),
+        }
+    }
+}
+
+#[derive(Bundle)]
+pub struct PickableTowerPartBundle {
+    tower_part: TowerPart,
+    pickable_mesh: PickableMesh,
+    interaction: Interaction,
+    focus_policy: FocusPolicy,
+    hover: Hover,
+}
+
+impl PickableTowerPartBundle {
+    pub fn new(tower: Entity) -> Self {
+        Self {
+            tower_part: TowerPart { tower },
+            pickable_mesh: PickableMesh::default(),
+            interaction: Interaction::default(),
+            // let the build location underneath see the cursor too, so the builder can
+            // tell it's occupied
+            focus_policy: FocusPolicy::Pass,
+            hover: Hover::default(),
+        }
+    }
+}
+
+/// Node of a tower's scene that is turned towards the tower's target
+#[derive(Reflect, Component)]
+#[reflect(Component)]
+pub struct TurretHead {
+    pub tower: Entity,
+    /// Rotation of the node as authored, the turret's yaw is applied on top
+    pub rest: Quat,
+}
+
+impl FromWorld for TurretHead {
+    fn from_world(_world: &mut World) -> Self {
+        Self {
+            tower: Entity::from_raw(u32::MAX),
+            rest: Quat::IDENTITY,
+        }
+    }
+}
+
+/// Makes every mesh in the tower's scene pickable, so clicking the model selects the tower,
+/// and marks the turret node (if the tower has one)
+fn tower_scene_hook(tower: Entity, turret_node: Option<String>) -> SceneHook {
+    SceneHook::new(move |entity, cmds| {
+        if entity.contains::<Handle<Mesh>>() {
+            cmds.insert(PickableTowerPartBundle::new(tower));
+        }
+
+        if let (Some(turret_node), Some(name)) = (&turret_node, entity.get::<Name>()) {
+            if name.starts_with(turret_node.as_str()) {
+                cmds.insert(TurretHead {
+                    tower,
+                    rest: entity
+                        .get::<Transform>()
+                        .map_or(Quat::IDENTITY, |transform| transform.rotation),
+                });
+            }
+        }
+    })
+}
+
+/// Marks a tower whose scene has no node matching its `turret_node`, so it is only reported once
+#[derive(Component)]
+struct MissingTurretHead;
+
+/// Towers whose scene was hooked this frame and haven't been reported yet
+type NewlyHookedTower = (Added<SceneHooked>, Without<MissingTurretHead>);
+
+/// Warns about towers whose freshly hooked scene didn't have the configured turret node, the
+/// turret won't turn and the tower holds fire until its (invisible) aim happens to line up
+fn warn_missing_turret_heads(
+    mut commands: Commands,
+    towers: Query<(Entity, &TowerType, &Name), NewlyHookedTower>,
+    turret_heads: Query<&TurretHead>,
+    registry: Option<Res<TowerRegistry>>,
+) {
+    // towers are only built once the definitions have loaded
+    let registry = match registry {
+        Some(registry) => registry,
+        None => return,
+    };
+
+    for (entity, tower_type, name) in &towers {
+        let turret_node = match registry
+            .get(*tower_type)
+            .and_then(|blueprint| blueprint.definition.turret_node.as_deref())
+        {
+            Some(turret_node) => turret_node,
+            None => continue,
+        };
+
+        if turret_heads.iter().any(|head| head.tower == entity) {
+            continue;
+        }
+
+        warn!("{name}: no node named {turret_node:?} in its scene, the turret won't turn");
+        commands.entity(entity).insert(MissingTurretHead);
+    }
+}
+
+/// Height towers are raised above their build location
+pub const TOWER_HEIGHT_OFFSET: f32 = 0.1;
+
+/// Request to upgrade a tower to its next level, paid for from the wallet
+pub struct UpgradeTower(pub Entity);
+
+/// Request to sell a tower, refunding part of the gold invested in it
+pub struct SellTower(pub Entity);
+
 pub fn spawn_tower(
     commands: &mut Commands,
     tower_type: TowerType,
-    assets: &GameAssets,
+    blueprint: &TowerBlueprint,
     position: Vec3,
 ) -> Entity {
+    let definition = &blueprint.definition;
+
     // Tower spawn position needs a slight offset in the y axis
-    let offset_tower_position = Transform::from_xyz(position.x, position.y + 0.1, position.z);
+    let offset_tower_position =
+        Transform::from_xyz(position.x, position.y + TOWER_HEIGHT_OFFSET, position.z);
 
-    match tower_type {
-        TowerType::Cannon => commands.spawn((
+    let tower = commands
+        .spawn((
             SceneBundle {
-                scene: assets.tower_cannon_scene.clone(),
+                scene: blueprint.scene.clone(),
                 transform: offset_tower_position,
                 ..default()
             },
-            Name::new("Tower (Cannon)"),
-            TowerType::Cannon,
+            Name::new(format!("Tower ({})", definition.name)),
+            tower_type,
+            TargetingMode::default(),
             Tower {
-                shooting_timer: Timer::from_seconds(2.0, TimerMode::Repeating),
-                projectile_offset: Vec3::new(0.0, 0.6, 0.0),
-                range: 3.0,
-            },
-        )),
-        TowerType::Catapult => commands.spawn((
-            SceneBundle {
-                scene: assets.tower_catapult_scene.clone(),
-                transform: offset_tower_position,
-                ..default()
-            },
-            Name::new("Tower (Catapult)"),
-            TowerType::Catapult,
-            Tower {
-                shooting_timer: Timer::from_seconds(2.0, TimerMode::Repeating),
-                projectile_offset: Vec3::new(0.0, 0.6, 0.0),
-                range: 3.0,
-            },
-        )),
-        TowerType::Blaster => commands.spawn((
-            SceneBundle {
-                scene: assets.tower_blaster_scene.clone(),
-                transform: offset_tower_position,
+                shooting_timer: Timer::from_seconds(
+                    1.0 / definition.fire_rate,
+                    TimerMode::Repeating,
+                ),
+                projectile_offset: Vec3::from(definition.projectile_offset),
+                projectile_speed: definition.projectile_speed,
+                projectile_scene: blueprint.projectile_scene.clone(),
+                range: definition.range,
+                damage: definition.damage,
+                damage_type: definition.damage_type,
+                firing_model: definition.firing_model,
+                homing: definition.homing,
+                splash: definition.splash,
+                pierce: definition.pierce,
+                effects: definition.effects.clone(),
+                invested: definition.cost,
+                turn_rate: definition.turn_rate.to_radians(),
+                aim_tolerance: definition.aim_tolerance.to_radians(),
+                lead_targets: true,
                 ..default()
             },
-            Name::new("Tower (Blaster)"),
-            TowerType::Blaster,
-            Tower {
-                shooting_timer: Timer::from_seconds(2.0, TimerMode::Repeating),
-                projectile_offset: Vec3::new(0.0, 0.6, 0.0),
-                range: 3.0,
-            },
-        )),
-    }
-    .id()
+            DamageStats::default(),
+            SessionEntity,
+        ))
+        .id();
+
+    commands
+        .entity(tower)
+        .insert(tower_scene_hook(tower, definition.turret_node.clone()));
+
+    tower
 }
 
 fn tower_shooting(
     mut commands: Commands,
-    mut towers: Query<(Entity, &mut Tower, &GlobalTransform)>,
-    targets: Query<(&GlobalTransform, &NavAgent), With<Target>>,
-    game_assets: Res<GameAssets>,
+    mut towers: Query<(Entity, &mut Tower, &TargetingMode, &GlobalTransform)>,
+    targets: Query<(Entity, &GlobalTransform, &NavAgent, &Health), With<Target>>,
+    target_grid: Res<TargetGrid>,
+    mut stats: ResMut<ProjectileStats>,
+    mut diagnostics: ResMut<Diagnostics>,
     time: Res<Time>,
 ) {
-    for (tower_entity, mut tower, transform) in &mut towers {
+    let mut candidates = 0;
+
+    for (tower_entity, mut tower, targeting_mode, transform) in &mut towers {
         tower.shooting_timer.tick(time.delta());
 
+        // start a new volley, the first shot of which fires straight away
         if tower.shooting_timer.just_finished() {
-            let projectile_speed = 2.5; // move this to component
-            let projectile_spawn = transform.translation() + tower.projectile_offset;
-
-            let projectile_direction = targets
-                .iter()
-                // filter out targets that are out of range
-                .filter(|(target_transform, _)| {
-                    Vec3::distance(target_transform.translation(), projectile_spawn) <= tower.range
-                })
-                // order targets by distance, closest first
-                .min_by_key(|(target_transform, _)| {
-                    FloatOrd(Vec3::distance(
+            tower.shots_remaining = tower.firing_model.shots_per_volley();
+            tower.burst_timer = Timer::from_seconds(0.0, TimerMode::Once);
+        }
+
+        tower.burst_timer.tick(time.delta());
+
+        let projectile_speed = tower.projectile_speed;
+        let firing_model = tower.firing_model;
+        let lead_targets = tower.lead_targets;
+        let projectile_spawn = transform.translation() + tower.projectile_offset;
+
+        // only consider targets that are in range
+        let in_range = target_grid.query_range(projectile_spawn, tower.range);
+        candidates += in_range.len();
+
+        let predicted_target = in_range
+            .into_iter()
+            .filter_map(|(target, _)| targets.get(target).ok())
+            // pick the preferred target for this tower's targeting mode
+            .min_by_key(|(_, target_transform, nav_agent, health)| {
+                let translation = target_transform.translation();
+                targeting_mode.score(
+                    Vec3::distance(translation, projectile_spawn),
+                    nav_agent.remaining_distance(translation),
+                    health.current,
+                )
+            })
+            // lead the target along its remaining route, aiming straight at it if it can't be caught
+            .map(|(target, target_transform, nav_agent, _)| {
+                if !lead_targets {
+                    return (target, target_transform.translation());
+                }
+
+                let predicted_target = match firing_model {
+                    // lobbed shots take as long as their arc does, whatever the distance
+                    FiringModel::Lobbed {
+                        arc_height,
+                        gravity,
+                        ..
+                    } => ballistic_intercept(
+                        projectile_spawn,
+                        gravity,
+                        arc_height,
                         target_transform.translation(),
+                        nav_agent.move_speed,
+                        nav_agent.start_delay(),
+                        nav_agent.remaining_route(),
+                    ),
+                    FiringModel::Single | FiringModel::Rapid { .. } => route_intercept(
                         projectile_spawn,
-                    ))
-                })
-                // basic target movement prediction
-                .map(|(closest_target, nav_agent)| {
-                    let distance = Vec3::distance(closest_target.translation(), projectile_spawn);
-                    let time_to_target = distance / projectile_speed;
-                    let prediction_vector =
-                        closest_target.forward() * nav_agent.move_speed * time_to_target;
-
-                    // predicted location - projectile spawn = projectile direction vector
-                    return closest_target.translation() + prediction_vector - projectile_spawn;
-                });
+                        projectile_speed,
+                        target_transform.translation(),
+                        nav_agent.move_speed,
+                        nav_agent.start_delay(),
+                        nav_agent.remaining_route(),
+                    )
+                    .unwrap_or_else(|| target_transform.translation()),
+                };
+
+                (target, predicted_target)
+            });
+
+        // nothing in range, drop the rest of the volley
+        let (target, predicted_target) = match predicted_target {
+            Some(predicted_target) => predicted_target,
+            None => {
+                tower.shots_remaining = 0;
+                continue;
+            }
+        };
+
+        // swing the turret towards the shot, no faster than its turn rate
+        let aim = predicted_target - projectile_spawn;
+        let target_yaw = aim.x.atan2(aim.z);
+        let max_turn = tower.turn_rate * time.delta_seconds();
+        let turn = wrap_angle(target_yaw - tower.turret_yaw).clamp(-max_turn, max_turn);
+        tower.turret_yaw = wrap_angle(tower.turret_yaw + turn);
+
+        if tower.shots_remaining == 0 || !tower.burst_timer.finished() {
+            continue;
+        }
+
+        // hold fire until the turret is lined up
+        if wrap_angle(target_yaw - tower.turret_yaw).abs() > tower.aim_tolerance {
+            continue;
+        }
+
+        tower.shots_remaining -= 1;
+        stats.fired += 1;
+        tower.burst_timer =
+            Timer::from_seconds(tower.firing_model.shot_interval(), TimerMode::Once);
+
+        // predicted location - projectile spawn = projectile direction vector
+        let direction = predicted_target - projectile_spawn;
+
+        let projectile = commands
+            .spawn((
+                SceneBundle {
+                    scene: tower.projectile_scene.clone(),
+                    transform: Transform::from_translation(projectile_spawn)
+                        .looking_at(predicted_target, Vec3::Y),
+                    ..default()
+                },
+                Name::new("Bullet"),
+                Lifetime {
+                    timer: Timer::from_seconds(10.0, TimerMode::Once),
+                },
+                Projectile {
+                    direction,
+                    speed: projectile_speed,
+                    damage: tower.damage,
+                    damage_type: tower.damage_type,
+                    origin: Some(tower_entity),
+                    pierce: tower.pierce,
+                    effects: tower.effects.clone(),
+                    ..default()
+                },
+                SessionEntity,
+            ))
+            .id();
 
-            if let Some(bullet_direction) = projectile_direction {
-                commands.entity(tower_entity).with_children(|commands| {
-                    commands.spawn((
-                        SceneBundle {
-                            scene: game_assets.cannon_ball_scene.clone(),
-                            transform: Transform::from_translation(tower.projectile_offset),
-                            ..default()
-                        },
-                        Name::new("Bullet"),
-                        Lifetime {
-                            timer: Timer::from_seconds(10.0, TimerMode::Once),
-                        },
-                        Projectile {
-                            direction: bullet_direction,
-                            speed: projectile_speed,
-                            damage: 1,
-                        },
-                        PhysicsBundle::moving_entity_cube(Vec3::new(0.2, 0.2, 0.)),
-                    ));
+        match tower.firing_model {
+            FiringModel::Lobbed {
+                arc_height,
+                gravity,
+            } => {
+                // enemies float above their route, bring the shot down on the ground beneath
+                let landing = predicted_target - WAYPOINT_OFFSET;
+                let (velocity, _) =
+                    ballistic_launch(projectile_spawn, landing, gravity, arc_height);
+
+                commands.entity(projectile).insert(Ballistic {
+                    velocity,
+                    gravity,
+                    ground: landing.y,
                 });
             }
+            FiringModel::Single | FiringModel::Rapid { .. } => {
+                commands
+                    .entity(projectile)
+                    .insert(PhysicsBundle::moving_entity_cube(Vec3::new(0.2, 0.2, 0.)));
+
+                if let Some(homing) = tower.homing {
+                    commands.entity(projectile).insert(Homing {
+                        target: Some(target),
+                        turn_rate: homing.turn_rate.to_radians(),
+                        on_target_lost: homing.on_target_lost,
+                    });
+                }
+            }
+        }
+
+        if let Some(splash) = tower.splash {
+            commands.entity(projectile).insert(splash);
+        }
+    }
+
+    diagnostics.add_measurement(TARGET_CANDIDATES, || candidates as f64);
+}
+
+/// Wraps an angle in radians into `[-PI, PI)`
+fn wrap_angle(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(TAU) - PI
+}
+
+fn rotate_turret_heads(
+    mut turret_heads: Query<(&TurretHead, &mut Transform)>,
+    towers: Query<&Tower>,
+) {
+    for (turret_head, mut transform) in &mut turret_heads {
+        if let Ok(tower) = towers.get(turret_head.tower) {
+            transform.rotation = Quat::from_rotation_y(tower.turret_yaw) * turret_head.rest;
+        }
+    }
+}
+
+fn upgrade_towers(
+    mut commands: Commands,
+    mut events: EventReader<UpgradeTower>,
+    mut towers: Query<(&mut Tower, &TowerType, &mut Handle<Scene>)>,
+    registry: Res<TowerRegistry>,
+    mut wallet: ResMut<Wallet>,
+) {
+    for UpgradeTower(entity) in events.iter() {
+        let (mut tower, tower_type, mut scene) = match towers.get_mut(*entity) {
+            Ok(tower) => tower,
+            Err(_) => continue,
+        };
+
+        let blueprint = match registry.get(*tower_type) {
+            Some(blueprint) => blueprint,
+            None => {
+                warn!("No tower definition for {:?}, can't upgrade", tower_type);
+                continue;
+            }
+        };
+        let upgrade = match blueprint.next_upgrade(tower.level) {
+            Some(upgrade) => upgrade,
+            None => {
+                info!("Tower is already at max level");
+                continue;
+            }
+        };
+
+        // refuse to upgrade if we can't pay for it
+        if !wallet.try_spend(upgrade.cost) {
+            info!(
+                "Not enough gold to upgrade tower ({} < {})",
+                wallet.gold, upgrade.cost
+            );
+            continue;
         }
+
+        // apply the new stats in place, keeping the current shot cycle going
+        tower
+            .shooting_timer
+            .set_duration(Duration::from_secs_f32(1.0 / upgrade.fire_rate));
+        tower.range = upgrade.range;
+        tower.damage = upgrade.damage;
+        if let Some(pierce) = upgrade.pierce {
+            tower.pierce = pierce;
+        }
+
+        // swapping the scene handle respawns the model, which then needs hooking up again
+        if let Some(upgrade_scene) = &blueprint.upgrade_scenes[tower.level] {
+            *scene = upgrade_scene.clone();
+            commands.entity(*entity).remove::<SceneHooked>();
+        }
+
+        tower.level += 1;
+        tower.invested += upgrade.cost;
+    }
+}
+
+fn sell_towers(
+    mut commands: Commands,
+    mut events: EventReader<SellTower>,
+    towers: Query<&Tower, Without<Despawn>>,
+    refund: Res<SellRefund>,
+    mut wallet: ResMut<Wallet>,
+) {
+    for SellTower(entity) in events.iter() {
+        // ignore repeated requests for a tower that is already on its way out
+        let tower = match towers.get(*entity) {
+            Ok(tower) => tower,
+            Err(_) => continue,
+        };
+
+        wallet.earn(refund.refund(tower.invested));
+        commands.entity(*entity).insert(Despawn);
     }
 }
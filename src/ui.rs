@@ -1,13 +1,50 @@
 use bevy::prelude::*;
 
-use crate::TowerType;
+use crate::{
+    common::{DamageStats, Despawn},
+    goal::Lives,
+    state::{GameState, SessionEntity},
+    wave::{WaveCleared, WaveStarted},
+    SelectedTower, SellRefund, SellTower, TargetingMode, Tower, TowerRegistry, TowerType,
+    UpgradeTower, Wallet,
+};
 
 pub struct UiPlugin;
 
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
         app.add_startup_system_to_stage(StartupStage::PreStartup, load_ui_assets)
-            .add_startup_system(ui);
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(ui)
+                    .with_system(tower_panel),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(update_gold_text)
+                    .with_system(update_lives_text)
+                    .with_system(update_wave_text)
+                    .with_system(update_tower_panel)
+                    .with_system(tower_panel_buttons),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::MainMenu).with_system(main_menu))
+            .add_system_set(SystemSet::on_exit(GameState::MainMenu).with_system(close_menu))
+            .add_system_set(SystemSet::on_enter(GameState::Paused).with_system(pause_menu))
+            .add_system_set(SystemSet::on_exit(GameState::Paused).with_system(close_menu))
+            .add_system_set(SystemSet::on_enter(GameState::Victory).with_system(victory_menu))
+            .add_system_set(SystemSet::on_exit(GameState::Victory).with_system(close_menu))
+            .add_system_set(SystemSet::on_enter(GameState::Defeat).with_system(defeat_menu))
+            .add_system_set(SystemSet::on_exit(GameState::Defeat).with_system(close_menu));
+
+        // Menus only exist in these states, so that is the only time their buttons are live
+        for state in [
+            GameState::MainMenu,
+            GameState::Paused,
+            GameState::Victory,
+            GameState::Defeat,
+        ] {
+            app.add_system_set(SystemSet::on_update(state).with_system(menu_buttons));
+        }
     }
 }
 
@@ -16,35 +53,46 @@ impl Plugin for UiPlugin {
 */
 #[derive(Resource)]
 pub struct UiAssets {
-    pub tower_cannon_icon: Handle<Image>,
-    pub tower_catapult_icon: Handle<Image>,
-    pub tower_blaster_icon: Handle<Image>,
+    pub font: Handle<Font>,
 }
 fn load_ui_assets(mut commands: Commands, assets: Res<AssetServer>) {
     commands.insert_resource(UiAssets {
-        tower_cannon_icon: assets.load("image/tower-cannon.png"),
-        tower_catapult_icon: assets.load("image/tower-catapult.png"),
-        tower_blaster_icon: assets.load("image/tower-blaster.png"),
+        font: assets.load("fonts/FiraSans-Bold.ttf"),
     });
 }
 
 pub struct RootUi;
 
-fn ui(mut commands: Commands, ui_assets: Res<UiAssets>) {
-    let towers = [TowerType::Cannon, TowerType::Catapult, TowerType::Blaster];
+#[derive(Component)]
+pub struct GoldText;
+
+#[derive(Component)]
+pub struct LivesText;
 
+#[derive(Component)]
+pub struct WaveText;
+
+fn ui(mut commands: Commands, ui_assets: Res<UiAssets>, tower_registry: Res<TowerRegistry>) {
     commands
-        .spawn(NodeBundle {
-            style: Style {
-                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
-                padding: UiRect::all(Val::Px(16.0)),
-                justify_content: JustifyContent::FlexEnd,
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    padding: UiRect::all(Val::Px(16.0)),
+                    justify_content: JustifyContent::FlexEnd,
+                    ..default()
+                },
                 ..default()
             },
-            ..default()
-        })
+            SessionEntity,
+        ))
         .with_children(|commands| {
-            for tower_type in towers {
+            for &tower_type in tower_registry.tower_types() {
+                let blueprint = match tower_registry.get(tower_type) {
+                    Some(blueprint) => blueprint,
+                    None => continue,
+                };
+
                 commands.spawn((
                     ButtonBundle {
                         style: Style {
@@ -53,15 +101,453 @@ fn ui(mut commands: Commands, ui_assets: Res<UiAssets>) {
                             margin: UiRect::all(Val::Px(16.0)),
                             ..default()
                         },
-                        image: match tower_type {
-                            TowerType::Cannon => ui_assets.tower_cannon_icon.clone().into(),
-                            TowerType::Catapult => ui_assets.tower_catapult_icon.clone().into(),
-                            TowerType::Blaster => ui_assets.tower_blaster_icon.clone().into(),
-                        },
+                        image: blueprint.icon.clone().into(),
                         ..default()
                     },
                     tower_type,
                 ));
             }
         });
+
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: ui_assets.font.clone(),
+                font_size: 32.0,
+                color: Color::rgb(1.0, 0.84, 0.0),
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                left: Val::Px(32.0),
+                top: Val::Px(32.0),
+                ..default()
+            },
+            ..default()
+        }),
+        GoldText,
+        SessionEntity,
+    ));
+
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: ui_assets.font.clone(),
+                font_size: 32.0,
+                color: Color::rgb(0.9, 0.2, 0.2),
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                left: Val::Px(32.0),
+                top: Val::Px(72.0),
+                ..default()
+            },
+            ..default()
+        }),
+        LivesText,
+        SessionEntity,
+    ));
+
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: ui_assets.font.clone(),
+                font_size: 32.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                left: Val::Px(32.0),
+                top: Val::Px(112.0),
+                ..default()
+            },
+            ..default()
+        }),
+        WaveText,
+        SessionEntity,
+    ));
+}
+
+fn update_gold_text(
+    wallet: Res<Wallet>,
+    mut gold_text: Query<&mut Text, With<GoldText>>,
+    added: Query<(), Added<GoldText>>,
+) {
+    if !wallet.is_changed() && added.is_empty() {
+        return;
+    }
+
+    for mut text in &mut gold_text {
+        text.sections[0].value = format!("Gold: {}", wallet.gold);
+    }
+}
+
+fn update_lives_text(
+    lives: Res<Lives>,
+    mut lives_text: Query<&mut Text, With<LivesText>>,
+    added: Query<(), Added<LivesText>>,
+) {
+    if !lives.is_changed() && added.is_empty() {
+        return;
+    }
+
+    for mut text in &mut lives_text {
+        text.sections[0].value = format!("Lives: {}", lives.value);
+    }
+}
+
+fn update_wave_text(
+    mut wave_started: EventReader<WaveStarted>,
+    mut wave_cleared: EventReader<WaveCleared>,
+    mut wave_text: Query<&mut Text, With<WaveText>>,
+) {
+    for event in wave_started.iter() {
+        for mut text in &mut wave_text {
+            text.sections[0].value = format!("Wave {}/{}", event.index + 1, event.total);
+        }
+    }
+
+    for event in wave_cleared.iter() {
+        for mut text in &mut wave_text {
+            text.sections[0].value = format!("Wave {}/{} cleared!", event.index + 1, event.total);
+        }
+    }
+}
+
+/*
+    TOWER PANEL
+*/
+
+#[derive(Component)]
+pub struct TowerPanel;
+
+#[derive(Component)]
+pub struct TowerInfoText;
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum TowerPanelButton {
+    Upgrade,
+    Sell,
+    Targeting,
+}
+
+/// Text inside a tower panel button, relabelled to match the selected tower
+#[derive(Component)]
+pub struct TowerPanelLabel(TowerPanelButton);
+
+fn tower_panel(mut commands: Commands, ui_assets: Res<UiAssets>) {
+    let text_style = TextStyle {
+        font: ui_assets.font.clone(),
+        font_size: 24.0,
+        color: Color::WHITE,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Px(32.0),
+                        bottom: Val::Px(32.0),
+                        ..default()
+                    },
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(16.0)),
+                    // hidden until a tower is selected
+                    display: Display::None,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                ..default()
+            },
+            TowerPanel,
+            Name::new("Tower Panel"),
+            SessionEntity,
+        ))
+        .with_children(|commands| {
+            commands.spawn((
+                TextBundle::from_section("", text_style.clone()).with_style(Style {
+                    margin: UiRect::bottom(Val::Px(8.0)),
+                    ..default()
+                }),
+                TowerInfoText,
+            ));
+
+            for button in [
+                TowerPanelButton::Upgrade,
+                TowerPanelButton::Sell,
+                TowerPanelButton::Targeting,
+            ] {
+                commands
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(240.0), Val::Px(40.0)),
+                                margin: UiRect::all(Val::Px(4.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: Color::hsl(209.0, 0.45, 0.35).into(),
+                            ..default()
+                        },
+                        button,
+                    ))
+                    .with_children(|commands| {
+                        commands.spawn((
+                            TextBundle::from_section("", text_style.clone()),
+                            TowerPanelLabel(button),
+                        ));
+                    });
+            }
+        });
+}
+
+fn update_tower_panel(
+    selected: Res<SelectedTower>,
+    towers: Query<(&Tower, &TowerType, &TargetingMode, &DamageStats)>,
+    tower_registry: Option<Res<TowerRegistry>>,
+    sell_refund: Res<SellRefund>,
+    mut panel: Query<&mut Style, With<TowerPanel>>,
+    mut info_text: Query<&mut Text, With<TowerInfoText>>,
+    mut labels: Query<(&mut Text, &TowerPanelLabel), Without<TowerInfoText>>,
+) {
+    let selection = selected
+        .0
+        .and_then(|tower| towers.get(tower).ok())
+        .zip(tower_registry);
+
+    for mut style in &mut panel {
+        style.display = match selection {
+            Some(_) => Display::Flex,
+            None => Display::None,
+        };
+    }
+
+    let ((tower, tower_type, targeting_mode, damage_stats), tower_registry) = match selection {
+        Some(selection) => selection,
+        None => return,
+    };
+
+    let blueprint = match tower_registry.get(*tower_type) {
+        Some(blueprint) => blueprint,
+        None => return,
+    };
+    let definition = &blueprint.definition;
+
+    for mut text in &mut info_text {
+        text.sections[0].value = format!(
+            "{}\nLevel: {}/{}\nRange: {:.1}\nFire rate: {:.2}/s\nDamage dealt: {}\nKills: {}",
+            definition.name,
+            tower.level + 1,
+            definition.upgrades.len() + 1,
+            tower.range,
+            1.0 / tower.shooting_timer.duration().as_secs_f32(),
+            damage_stats.damage_dealt,
+            damage_stats.kills,
+        );
+    }
+
+    for (mut text, label) in &mut labels {
+        text.sections[0].value = match label.0 {
+            TowerPanelButton::Upgrade => match blueprint.next_upgrade(tower.level) {
+                Some(upgrade) => format!("Upgrade ({}g)", upgrade.cost),
+                None => "Max level".to_string(),
+            },
+            TowerPanelButton::Sell => format!("Sell ({}g)", sell_refund.refund(tower.invested)),
+            TowerPanelButton::Targeting => format!("Target: {:?}", targeting_mode),
+        };
+    }
+}
+
+fn tower_panel_buttons(
+    interaction: Query<(&Interaction, &TowerPanelButton), Changed<Interaction>>,
+    selected: Res<SelectedTower>,
+    mut targeting_modes: Query<&mut TargetingMode>,
+    mut upgrade_tower: EventWriter<UpgradeTower>,
+    mut sell_tower: EventWriter<SellTower>,
+) {
+    let tower = match selected.0 {
+        Some(tower) => tower,
+        None => return,
+    };
+
+    for (interaction, button) in &interaction {
+        if !matches!(interaction, Interaction::Clicked) {
+            continue;
+        }
+
+        match button {
+            TowerPanelButton::Upgrade => upgrade_tower.send(UpgradeTower(tower)),
+            TowerPanelButton::Sell => sell_tower.send(SellTower(tower)),
+            TowerPanelButton::Targeting => {
+                if let Ok(mut targeting_mode) = targeting_modes.get_mut(tower) {
+                    *targeting_mode = targeting_mode.next();
+                }
+            }
+        }
+    }
+}
+
+/*
+    MENUS
+*/
+
+#[derive(Component)]
+pub struct MenuUi;
+
+#[derive(Component, Clone, Copy)]
+pub enum MenuButton {
+    Play,
+    Resume,
+    Restart,
+    MainMenu,
+}
+
+impl MenuButton {
+    fn label(&self) -> &'static str {
+        match self {
+            MenuButton::Play => "Play",
+            MenuButton::Resume => "Resume",
+            MenuButton::Restart => "Restart",
+            MenuButton::MainMenu => "Main Menu",
+        }
+    }
+}
+
+fn main_menu(commands: Commands, ui_assets: Res<UiAssets>) {
+    spawn_menu(
+        commands,
+        &ui_assets,
+        "Bevy Tower Defense",
+        &[MenuButton::Play],
+    );
+}
+
+fn pause_menu(commands: Commands, ui_assets: Res<UiAssets>) {
+    spawn_menu(
+        commands,
+        &ui_assets,
+        "Paused",
+        &[MenuButton::Resume, MenuButton::MainMenu],
+    );
+}
+
+fn victory_menu(commands: Commands, ui_assets: Res<UiAssets>) {
+    spawn_menu(
+        commands,
+        &ui_assets,
+        "Victory!",
+        &[MenuButton::Restart, MenuButton::MainMenu],
+    );
+}
+
+fn defeat_menu(commands: Commands, ui_assets: Res<UiAssets>) {
+    spawn_menu(
+        commands,
+        &ui_assets,
+        "Defeat!",
+        &[MenuButton::Restart, MenuButton::MainMenu],
+    );
+}
+
+fn spawn_menu(mut commands: Commands, ui_assets: &UiAssets, title: &str, buttons: &[MenuButton]) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                ..default()
+            },
+            MenuUi,
+            Name::new("Menu"),
+        ))
+        .with_children(|commands| {
+            commands.spawn(
+                TextBundle::from_section(
+                    title,
+                    TextStyle {
+                        font: ui_assets.font.clone(),
+                        font_size: 64.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::all(Val::Px(24.0)),
+                    ..default()
+                }),
+            );
+
+            for button in buttons {
+                commands
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(240.0), Val::Px(56.0)),
+                                margin: UiRect::all(Val::Px(8.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: Color::hsl(209.0, 0.45, 0.35).into(),
+                            ..default()
+                        },
+                        *button,
+                    ))
+                    .with_children(|commands| {
+                        commands.spawn(TextBundle::from_section(
+                            button.label(),
+                            TextStyle {
+                                font: ui_assets.font.clone(),
+                                font_size: 32.0,
+                                color: Color::WHITE,
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+fn close_menu(mut commands: Commands, menus: Query<Entity, With<MenuUi>>) {
+    for entity in menus.iter() {
+        commands.entity(entity).insert(Despawn);
+    }
+}
+
+fn menu_buttons(
+    interaction: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut state: ResMut<State<GameState>>,
+) {
+    for (interaction, button) in &interaction {
+        if !matches!(interaction, Interaction::Clicked) {
+            continue;
+        }
+
+        let result = match button {
+            MenuButton::Play | MenuButton::Restart => state.set(GameState::Playing),
+            MenuButton::Resume => state.pop(),
+            // replace rather than set, so a paused session underneath is exited as well
+            MenuButton::MainMenu => state.replace(GameState::MainMenu),
+        };
+
+        if let Err(err) = result {
+            warn!("Menu transition failed: {:?}", err);
+        }
+    }
 }
@@ -2,7 +2,7 @@ use bevy::{core_pipeline::clear_color::ClearColorConfig, prelude::*, render::cam
 use bevy_mod_picking::PickingCameraBundle;
 use leafwing_input_manager::prelude::*;
 
-use crate::input::Action;
+use crate::{input::Action, state::GameState};
 
 pub struct CameraPlugin;
 
@@ -10,7 +10,7 @@ impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<GameCamera>()
             .add_startup_system(spawn_main_camera)
-            .add_system(camera_controls);
+            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(camera_controls));
     }
 }
 
@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+
+use crate::{
+    common::{Despawn, Health},
+    level::LevelSettings,
+    navigation::NavAgentArrived,
+    state::GameState,
+    EnemyRegistry, EnemyType,
+};
+
+pub struct GoalPlugin;
+
+impl Plugin for GoalPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Lives>()
+            .init_resource::<Lives>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_lives))
+            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(enemy_leak));
+    }
+}
+
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct Lives {
+    pub value: u32,
+}
+
+impl Lives {
+    pub fn new(value: u32) -> Self {
+        Self { value }
+    }
+}
+
+fn reset_lives(mut lives: ResMut<Lives>, settings: Res<LevelSettings>) {
+    *lives = Lives::new(settings.starting_lives);
+}
+
+/// Enemies that reached the goal this frame and haven't been killed or leaked already
+type NewlyArrived = (Added<NavAgentArrived>, Without<Despawn>);
+
+fn enemy_leak(
+    mut commands: Commands,
+    leaked: Query<(Entity, &EnemyType, &Health), NewlyArrived>,
+    mut lives: ResMut<Lives>,
+    registry: Res<EnemyRegistry>,
+    mut state: ResMut<State<GameState>>,
+) {
+    for (entity, enemy_type, health) in &leaked {
+        // killed on the way in, it already paid out its bounty
+        if health.is_dead() {
+            continue;
+        }
+
+        commands.entity(entity).insert(Despawn);
+        if let Some(blueprint) = registry.get(enemy_type) {
+            lives.value = lives.value.saturating_sub(blueprint.definition.leak_damage);
+        }
+    }
+
+    // Out of lives, the game is lost
+    if lives.value == 0 {
+        let _ = state.set(GameState::Defeat);
+    }
+}
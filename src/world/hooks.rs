@@ -21,9 +21,11 @@ pub struct HookedSceneBundle {
 #[derive(Component, Debug)]
 pub struct SceneHooked;
 
+type HookFn = dyn Fn(&EntityRef, &mut EntityCommands) + Send + Sync + 'static;
+
 #[derive(Component)]
 pub struct SceneHook {
-    hook: Box<dyn Fn(&EntityRef, &mut EntityCommands) + Send + Sync + 'static>,
+    hook: Box<HookFn>,
 }
 impl SceneHook {
     pub fn new<F: Fn(&EntityRef, &mut EntityCommands) + Send + Sync + 'static>(hook: F) -> Self {
@@ -50,7 +52,7 @@ pub fn run_hooks(
             let mut cmd = cmds.entity(entity_ref.id());
             (hooked.hook)(&entity_ref, &mut cmd);
         }
-        
+
         cmds.entity(entity).insert(SceneHooked);
     }
 }
@@ -1,18 +1,26 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, reflect::TypeUuid};
+use serde::Deserialize;
 
 use crate::{
     assets::GameAssets,
+    data::{ensure_non_negative, ensure_positive, RonAssetLoader, Validate},
     hooks::{HookedSceneBundle, SceneHook},
     navigation::Waypoint,
-    spawner::get_spawn_point_for_enemy_type,
-    BuildLocation, EnemyType,
+    spawner::SpawnPoint,
+    state::{GameState, SessionEntity},
+    wave::{Wave, Waves},
+    BuildLocation,
 };
 
 pub struct LevelPlugin;
 
 impl Plugin for LevelPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(spawn_level);
+        app.register_type::<LevelSettings>()
+            .add_asset::<LevelDefinition>()
+            .add_asset_loader(RonAssetLoader::<LevelDefinition>::new(&["level.ron"]))
+            .add_system(apply_level_definition)
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(spawn_level));
     }
 }
 
@@ -21,6 +29,71 @@ const TOWER_BASE_LOCATION_NAME: &str = "grass";
 const SPAWN_LOCATION_NAME: &str = "spawn";
 const WAYPOINT_LOCATION_NAME: &str = "waypoint";
 
+/// Per level tuning that isn't part of the level scene itself
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct LevelSettings {
+    pub starting_lives: u32,
+    pub starting_gold: u32,
+}
+
+/// Designer tunable level setup, see `assets/data/level_0.level.ron`
+#[derive(Deserialize, TypeUuid)]
+#[uuid = "90bf8e83-3b65-4ca5-898e-40057b09ff38"]
+pub struct LevelDefinition {
+    pub starting_lives: u32,
+    pub starting_gold: u32,
+    pub waves: Vec<Wave>,
+}
+
+impl Validate for LevelDefinition {
+    fn validate(&self) -> Result<(), String> {
+        ensure_positive("Level", "starting_lives", self.starting_lives as f32)?;
+        ensure_positive("Level", "starting_gold", self.starting_gold as f32)?;
+
+        // with nothing to clear the level could never be won
+        if self.waves.is_empty() {
+            return Err("Level: needs at least one wave".to_string());
+        }
+
+        for (index, wave) in self.waves.iter().enumerate() {
+            let owner = format!("Wave {}", index + 1);
+            ensure_non_negative(&owner, "delay", wave.delay)?;
+
+            for group in &wave.groups {
+                ensure_non_negative(&owner, "spacing", group.spacing)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Replaces the level settings and waves whenever the level definition (re)loads
+fn apply_level_definition(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<LevelDefinition>>,
+    definitions: Res<Assets<LevelDefinition>>,
+) {
+    for event in events.iter() {
+        let handle = match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => handle,
+            AssetEvent::Removed { .. } => continue,
+        };
+
+        let definition = match definitions.get(handle) {
+            Some(definition) => definition,
+            None => continue,
+        };
+
+        commands.insert_resource(LevelSettings {
+            starting_lives: definition.starting_lives,
+            starting_gold: definition.starting_gold,
+        });
+        commands.insert_resource(Waves(definition.waves.clone()));
+    }
+}
+
 fn spawn_level(mut commands: Commands, game_assets: Res<GameAssets>) {
     // Spawn Level
     commands.spawn((
@@ -30,7 +103,7 @@ fn spawn_level(mut commands: Commands, game_assets: Res<GameAssets>) {
                 ..default()
             },
             hook: SceneHook::new(|entity, cmds| {
-                entity.get::<Name>().map(|name| {
+                if let Some(name) = entity.get::<Name>() {
                     /*
                     Attach required components for marked tiles
                     */
@@ -42,9 +115,8 @@ fn spawn_level(mut commands: Commands, game_assets: Res<GameAssets>) {
 
                     if name.starts_with(SPAWN_LOCATION_NAME) {
                         let data: Vec<&str> = name.split(".").collect();
-                        cmds.insert(get_spawn_point_for_enemy_type(
+                        cmds.insert(SpawnPoint::new(
                             data.get(1).unwrap().parse::<u32>().unwrap(),
-                            EnemyType::EnemyBasic,
                         ))
                         .insert(Name::new("SpawnPoint"));
                     }
@@ -57,9 +129,10 @@ fn spawn_level(mut commands: Commands, game_assets: Res<GameAssets>) {
                         })
                         .insert(Name::new("Waypoint"));
                     }
-                });
+                }
             }),
         },
         Name::new("Level"),
+        SessionEntity,
     ));
 }
@@ -1,16 +1,19 @@
 use bevy::{app::PluginGroupBuilder, prelude::*};
 
 use self::{
-    camera::CameraPlugin, hooks::HookPlugin, level::LevelPlugin, lighting::LightingPlugin,
-    navigation::NavigationPlugin, spawner::SpawnerPlugin,
+    camera::CameraPlugin, goal::GoalPlugin, hooks::HookPlugin, level::LevelPlugin,
+    lighting::LightingPlugin, navigation::NavigationPlugin, spawner::SpawnerPlugin,
+    wave::WavePlugin,
 };
 
 pub mod camera;
+pub mod goal;
 pub mod hooks;
 pub mod level;
 pub mod lighting;
 pub mod navigation;
 pub mod spawner;
+pub mod wave;
 
 pub struct DefaultWorldPlugins;
 impl PluginGroup for DefaultWorldPlugins {
@@ -22,5 +25,7 @@ impl PluginGroup for DefaultWorldPlugins {
             .add(CameraPlugin)
             .add(NavigationPlugin)
             .add(SpawnerPlugin)
+            .add(WavePlugin)
+            .add(GoalPlugin)
     }
 }
@@ -1,6 +1,6 @@
 use bevy::{prelude::*, utils::HashMap};
 
-use crate::spawner::SpawnPoint;
+use crate::{spawner::SpawnPoint, state::GameState};
 
 pub struct NavigationPlugin;
 
@@ -8,10 +8,15 @@ impl Plugin for NavigationPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<NavAgent>()
             .register_type::<Waypoint>()
-            .add_system_to_stage(CoreStage::PreUpdate, init_nav_routes)
-            .add_system_to_stage(CoreStage::PreUpdate, init_nav_agents)
-            .add_system_to_stage(CoreStage::PreUpdate, update_nav_agent_destination)
-            .add_system(move_nav_agents);
+            // State sets only run in Update, where the GameState driver lives, so they are
+            // ordered here rather than moved to an earlier stage
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(init_nav_routes)
+                    .with_system(init_nav_agents.after(init_nav_routes))
+                    .with_system(update_nav_agent_destination.after(init_nav_agents))
+                    .with_system(move_nav_agents.after(update_nav_agent_destination)),
+            );
     }
 }
 
@@ -25,9 +30,46 @@ pub struct NavAgent {
     pub destination: Vec3,
 }
 
+impl NavAgent {
+    /// Waypoints still to be visited, in order, starting with the current destination
+    pub fn remaining_route(&self) -> impl Iterator<Item = Vec3> + Clone + '_ {
+        // there is no destination until the agent sets off, it is taken from the route then
+        let destination = self.delay_timer.finished().then_some(self.destination);
+
+        // the route is consumed from the back, so walk it in reverse
+        destination
+            .into_iter()
+            .chain(self.route.iter().rev().copied())
+    }
+
+    /// Seconds until the agent sets off, 0 once it is on its way
+    pub fn start_delay(&self) -> f32 {
+        if self.delay_timer.finished() {
+            0.0
+        } else {
+            self.delay_timer.remaining_secs()
+        }
+    }
+
+    /// Distance left to travel along the route from `position`, lower means further along
+    pub fn remaining_distance(&self, position: Vec3) -> f32 {
+        let (remaining, _) = self
+            .remaining_route()
+            .fold((0.0, position), |(total, from), to| {
+                (total + from.distance(to), to)
+            });
+
+        remaining
+    }
+}
+
 #[derive(Component)]
 pub struct NavAgentEnabled;
 
+/// Added to a NavAgent once it has reached the final waypoint of its route
+#[derive(Component)]
+pub struct NavAgentArrived;
+
 #[derive(Reflect, Component, Default)]
 #[reflect(Component)]
 pub struct NavRoute {
@@ -60,7 +102,7 @@ fn init_nav_routes(
             commands.entity(entity).insert(NavigationInitiated);
 
             // return a tuple of (spawn_id, (wp_id, wp_location))
-            return (wp.spawn_id, (wp.id, t.translation() + WAYPOINT_OFFSET));
+            (wp.spawn_id, (wp.id, t.translation() + WAYPOINT_OFFSET))
         })
         .fold(
             HashMap::default(),
@@ -74,7 +116,7 @@ fn init_nav_routes(
                     }
                 }
 
-                return map;
+                map
             },
         );
 
@@ -83,14 +125,11 @@ fn init_nav_routes(
 
     for (spawn_id, mut waypoints) in grouped {
         // Sort by waypoint id (ascending)
-        waypoints.sort_by(|(wp_a, _), (wp_b, _)| wp_a.cmp(&wp_b));
+        waypoints.sort_by_key(|(wp_id, _)| *wp_id);
 
         routes.insert(
-            spawn_id.clone(),
-            waypoints
-                .iter()
-                .map(|(_, location)| location.clone())
-                .collect(),
+            spawn_id,
+            waypoints.iter().map(|(_, location)| *location).collect(),
         );
     }
 
@@ -134,7 +173,10 @@ fn update_nav_agent_destination(
             match agent.route.pop() {
                 Some(destination) => agent.destination = destination,
                 None => {
-                    commands.entity(entity).remove::<NavAgentEnabled>();
+                    commands
+                        .entity(entity)
+                        .remove::<NavAgentEnabled>()
+                        .insert(NavAgentArrived);
                 }
             }
         }
@@ -1,8 +1,10 @@
 use bevy::prelude::*;
 
 use crate::{
-    assets::GameAssets,
+    common::Despawn,
     navigation::{NavRoute, WAYPOINT_OFFSET},
+    state::GameState,
+    wave::WaveGroup,
     *,
 };
 
@@ -11,7 +13,7 @@ pub struct SpawnerPlugin;
 impl Plugin for SpawnerPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<SpawnPoint>()
-            .add_system_to_stage(CoreStage::Update, spawner);
+            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(spawner));
     }
 }
 
@@ -19,56 +21,77 @@ impl Plugin for SpawnerPlugin {
 #[reflect(Component)]
 pub struct SpawnPoint {
     pub id: u32,
-    pub enemy_type: EnemyType,
+    /// Groups still to be spawned this wave, in order
+    pub queue: Vec<WaveGroup>,
     pub spawn_timer: Timer,
-    pub max_spawns: u32,
+    /// Number spawned from the group at the front of the queue
     pub num_spawned: u32,
 }
 
-#[derive(Component)]
-pub struct SpawnPointDisabled;
+impl SpawnPoint {
+    pub fn new(id: u32) -> Self {
+        Self { id, ..default() }
+    }
+
+    pub fn queue_groups(&mut self, groups: impl Iterator<Item = WaveGroup>) {
+        self.queue.extend(groups);
+    }
 
-pub fn get_spawn_point_for_enemy_type(id: u32, enemy_type: EnemyType) -> SpawnPoint {
-    SpawnPoint {
-        id,
-        enemy_type,
-        spawn_timer: Timer::from_seconds(3.0, TimerMode::Repeating),
-        max_spawns: 99,
-        ..default()
+    /// True once every queued group has been fully spawned
+    pub fn is_idle(&self) -> bool {
+        self.queue.is_empty()
     }
 }
 
 fn spawner(
     mut commands: Commands,
-    mut spawn_points: Query<
-        (Entity, &mut SpawnPoint, &NavRoute, &GlobalTransform),
-        Without<SpawnPointDisabled>,
-    >,
-    game_assets: Res<GameAssets>,
+    mut spawn_points: Query<(&mut SpawnPoint, &NavRoute, &GlobalTransform), Without<Despawn>>,
+    enemy_registry: Res<EnemyRegistry>,
     time: Res<Time>,
 ) {
-    for (entity, mut spawn_point, nav_route, sp_transform) in &mut spawn_points {
-        // if spawn point is maxed, mark and exit early
-        if spawn_point.num_spawned >= spawn_point.max_spawns {
-            commands.entity(entity).insert(SpawnPointDisabled);
-            return;
+    for (mut spawn_point, nav_route, sp_transform) in &mut spawn_points {
+        // move past finished groups first, so an empty group never spawns anything
+        while spawn_point
+            .queue
+            .first()
+            .is_some_and(|group| spawn_point.num_spawned >= group.count)
+        {
+            spawn_point.queue.remove(0);
+            spawn_point.num_spawned = 0;
         }
 
-        // tick the spawn point timer
-        spawn_point.spawn_timer.tick(time.delta());
+        let group = match spawn_point.queue.first() {
+            Some(group) => group.clone(),
+            None => continue,
+        };
 
-        // spawn an entity at the spawn point if the timer just finished
-        if spawn_point.spawn_timer.just_finished() {
-            spawn_enemy(
-                &mut commands,
-                sp_transform.translation() + WAYPOINT_OFFSET,
-                spawn_point.enemy_type,
-                &nav_route.route,
-                &game_assets,
-            );
+        // most likely a typo in the waves, skip the whole group rather than warn per enemy
+        let blueprint = match enemy_registry.get(&group.enemy_type) {
+            Some(blueprint) => blueprint,
+            None => {
+                warn!("No enemy definition for {:?}, skipping", group.enemy_type);
+                spawn_point.queue.remove(0);
+                spawn_point.num_spawned = 0;
+                continue;
+            }
+        };
 
-            // increment spawn count
-            spawn_point.num_spawned += 1;
+        // tick the spawn point timer, wait for the spacing of the last spawn to pass
+        spawn_point.spawn_timer.tick(time.delta());
+        if !spawn_point.spawn_timer.finished() {
+            continue;
         }
+
+        spawn_enemy(
+            &mut commands,
+            sp_transform.translation() + WAYPOINT_OFFSET,
+            &group.enemy_type,
+            &nav_route.route,
+            blueprint,
+        );
+
+        // the spacing also holds back the first spawn of the next group
+        spawn_point.num_spawned += 1;
+        spawn_point.spawn_timer = Timer::from_seconds(group.spacing, TimerMode::Once);
     }
 }
@@ -0,0 +1,157 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    common::Despawn, navigation::NavRoute, spawner::SpawnPoint, state::GameState, EnemyType,
+};
+
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<WaveGroup>()
+            .register_type::<WaveTracker>()
+            .add_event::<WaveStarted>()
+            .add_event::<WaveCleared>()
+            .init_resource::<Waves>()
+            .init_resource::<WaveTracker>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_waves))
+            // A spawner keeps its finished group queued until the frame after the last spawn,
+            // by then the spawned enemy exists, so the wave can't be cleared early
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(start_next_wave)
+                    .with_system(check_wave_cleared),
+            );
+    }
+}
+
+/// A run of a single enemy type, spawned one after the other
+#[derive(Reflect, FromReflect, Deserialize, Clone, Default)]
+pub struct WaveGroup {
+    /// Spawn point to use, `None` (or left out) spawns the group at every spawn point
+    #[serde(default)]
+    pub spawn_id: Option<u32>,
+    pub enemy_type: EnemyType,
+    pub count: u32,
+    /// Seconds between each spawn in the group
+    pub spacing: f32,
+}
+
+#[derive(Deserialize, Clone, Default)]
+pub struct Wave {
+    /// Seconds to wait before this wave starts
+    pub delay: f32,
+    /// Groups are spawned in order, per spawn point
+    pub groups: Vec<WaveGroup>,
+}
+
+/// The ordered list of waves for the current level, taken from its `LevelDefinition`
+#[derive(Resource, Default)]
+pub struct Waves(pub Vec<Wave>);
+
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct WaveTracker {
+    /// Index of the next wave to start
+    pub next: usize,
+    pub delay_timer: Timer,
+    pub in_progress: bool,
+}
+
+pub struct WaveStarted {
+    pub index: usize,
+    pub total: usize,
+}
+
+pub struct WaveCleared {
+    pub index: usize,
+    pub total: usize,
+}
+
+fn reset_waves(mut tracker: ResMut<WaveTracker>, waves: Res<Waves>) {
+    *tracker = WaveTracker {
+        delay_timer: delay_timer_for(&waves, 0),
+        ..default()
+    };
+}
+
+fn delay_timer_for(waves: &Waves, index: usize) -> Timer {
+    let delay = waves.0.get(index).map_or(0.0, |wave| wave.delay);
+    Timer::from_seconds(delay, TimerMode::Once)
+}
+
+/// Spawn points the spawner drains, only routed ones in a level that isn't being torn down
+pub type ActiveSpawnPoint = (With<NavRoute>, Without<Despawn>);
+
+fn start_next_wave(
+    mut tracker: ResMut<WaveTracker>,
+    mut spawn_points: Query<&mut SpawnPoint, ActiveSpawnPoint>,
+    mut wave_started: EventWriter<WaveStarted>,
+    waves: Res<Waves>,
+    time: Res<Time>,
+) {
+    if tracker.in_progress || spawn_points.is_empty() {
+        return;
+    }
+
+    let wave = match waves.0.get(tracker.next) {
+        Some(wave) => wave,
+        None => return,
+    };
+
+    tracker.delay_timer.tick(time.delta());
+    if !tracker.delay_timer.finished() {
+        return;
+    }
+
+    // Hand every spawn point the groups it is responsible for this wave
+    for mut spawn_point in &mut spawn_points {
+        let id = spawn_point.id;
+        spawn_point.queue_groups(
+            wave.groups
+                .iter()
+                .filter(|group| group.spawn_id.is_none() || group.spawn_id == Some(id))
+                .cloned(),
+        );
+    }
+
+    wave_started.send(WaveStarted {
+        index: tracker.next,
+        total: waves.0.len(),
+    });
+
+    tracker.in_progress = true;
+}
+
+fn check_wave_cleared(
+    mut tracker: ResMut<WaveTracker>,
+    spawn_points: Query<&SpawnPoint, ActiveSpawnPoint>,
+    enemies: Query<(), (With<EnemyType>, Without<Despawn>)>,
+    mut wave_cleared: EventWriter<WaveCleared>,
+    mut state: ResMut<State<GameState>>,
+    waves: Res<Waves>,
+) {
+    if !tracker.in_progress {
+        return;
+    }
+
+    // The wave is cleared once everything has been spawned and nothing is left alive
+    if !enemies.is_empty() || spawn_points.iter().any(|sp| !sp.is_idle()) {
+        return;
+    }
+
+    wave_cleared.send(WaveCleared {
+        index: tracker.next,
+        total: waves.0.len(),
+    });
+
+    tracker.next += 1;
+    tracker.in_progress = false;
+    tracker.delay_timer = delay_timer_for(&waves, tracker.next);
+
+    // That was the final wave, the level is won
+    if tracker.next >= waves.0.len() {
+        let _ = state.set(GameState::Victory);
+    }
+}
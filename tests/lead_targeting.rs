@@ -0,0 +1,148 @@
+//! Headless check that towers leading their target along its route miss less than towers
+//! aiming straight at it, measured through `ProjectileStats`
+//!
+//! The intercept models the `NavAgent` start delay and its straight legs between waypoints,
+//! but not everything about how agents move, so led shots can still miss:
+//! - agents don't turn on the spot, their heading lerps towards the next waypoint at
+//!   `turn_speed`, so they round every corner instead of following the route exactly
+//! - agents switch to the next waypoint once they are within a tolerance of the current one,
+//!   cutting each corner short
+
+use std::time::{Duration, Instant};
+
+use bevy::{diagnostic::DiagnosticsPlugin, prelude::*};
+use bevy_rapier3d::prelude::*;
+use bevy_tower_defense::{
+    common::{CommonPlugin, DamageStats},
+    damage::{DamagePlugin, Defense},
+    level::LevelSettings,
+    navigation::NavigationPlugin,
+    physics::PhysicsBundle,
+    projectile::{Projectile, ProjectilePlugin, ProjectileStats},
+    spatial::SpatialPlugin,
+    state::GameState,
+    status::StatusPlugin,
+    EconomyPlugin, EnemyBundle, TargetingMode, Tower, TowerPlugin, TowerRegistry,
+};
+
+const TIMESTEP: f32 = 1.0 / 60.0;
+
+/// Enemies are spawned this often, far enough apart that only one is in range at a time
+const SPAWN_INTERVAL: f32 = 3.0;
+const SPAWN_COUNT: u32 = 12;
+/// Time after the last spawn for it to walk past the tower and for every shot to resolve
+const SETTLE_TIME: f32 = 15.0;
+
+/// Runs a single tower against a stream of enemies that cross in front of it, turn a corner
+/// and walk away, returning the share of its shots that missed
+fn miss_rate(lead_targets: bool) -> (f32, u32) {
+    let mut app = App::new();
+    app.add_plugin(CorePlugin::default())
+        .add_plugin(TransformPlugin)
+        .add_plugin(HierarchyPlugin)
+        .add_plugin(DiagnosticsPlugin)
+        .add_plugin(AssetPlugin::default())
+        // rapier looks these up for scene colliders, there are none here
+        .add_asset::<Mesh>()
+        .add_asset::<Scene>()
+        // stepped by hand below, so every run sees exactly the same frames
+        .init_resource::<Time>()
+        .insert_resource(RapierConfiguration {
+            gravity: Vec3::ZERO,
+            ..default()
+        })
+        .add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
+        .add_state(GameState::Playing)
+        .add_plugin(CommonPlugin)
+        .add_plugin(DamagePlugin)
+        .add_plugin(StatusPlugin)
+        .add_plugin(ProjectilePlugin)
+        .add_plugin(SpatialPlugin)
+        .add_plugin(NavigationPlugin)
+        .add_plugin(EconomyPlugin)
+        .add_plugin(TowerPlugin)
+        .init_resource::<TowerRegistry>()
+        .init_resource::<LevelSettings>();
+
+    app.world.spawn((
+        Tower {
+            shooting_timer: Timer::from_seconds(0.5, TimerMode::Repeating),
+            // level with the enemies, so every shot flies flat
+            projectile_offset: Vec3::new(0.0, 0.5, 0.0),
+            projectile_speed: 3.0,
+            range: 4.0,
+            damage: 1,
+            turn_rate: std::f32::consts::TAU * 4.0,
+            aim_tolerance: std::f32::consts::PI,
+            lead_targets,
+            ..default()
+        },
+        TargetingMode::First,
+        DamageStats::default(),
+        TransformBundle::default(),
+    ));
+
+    // across the front of the tower, then round the corner and away down the side
+    let start = Vec3::new(-6.0, 0.5, 2.0);
+    let corner = Vec3::new(2.0, 0.5, 2.0);
+    let end = Vec3::new(2.0, 0.5, -8.0);
+
+    let mut now = Instant::now();
+    let mut spawned = 0;
+    let mut until_spawn = 0.0;
+    let frames = ((SPAWN_INTERVAL * SPAWN_COUNT as f32 + SETTLE_TIME) / TIMESTEP) as u32;
+
+    for _ in 0..frames {
+        until_spawn -= TIMESTEP;
+        if spawned < SPAWN_COUNT && until_spawn <= 0.0 {
+            app.world.spawn((
+                // sturdy enough to survive every shot, a dead target can't be missed
+                EnemyBundle::new(
+                    1000,
+                    2.0,
+                    3.6,
+                    // consumed from the back
+                    vec![end, corner],
+                    Defense::default(),
+                    PhysicsBundle::moving_entity_sphere(0.55),
+                ),
+                TransformBundle::from_transform(
+                    Transform::from_translation(start).looking_at(corner, Vec3::Y),
+                ),
+            ));
+            spawned += 1;
+            until_spawn = SPAWN_INTERVAL;
+        }
+
+        now += Duration::from_secs_f32(TIMESTEP);
+        app.world.resource_mut::<Time>().update_with_instant(now);
+        app.update();
+    }
+
+    let in_flight = app
+        .world
+        .query_filtered::<(), With<Projectile>>()
+        .iter(&app.world)
+        .count() as u32;
+    let stats = app.world.resource::<ProjectileStats>();
+
+    (stats.miss_rate(in_flight), stats.fired)
+}
+
+#[test]
+fn leading_targets_misses_less_than_aiming_straight_at_them() {
+    let (led, led_fired) = miss_rate(true);
+    let (straight, straight_fired) = miss_rate(false);
+
+    // enough shots for the rates to mean something
+    assert!(led_fired >= 20, "only {led_fired} shots fired with lead");
+    assert!(
+        straight_fired >= 20,
+        "only {straight_fired} shots fired without lead"
+    );
+
+    assert!(
+        led < straight / 2.0,
+        "miss rate with lead {led:.2} is not clearly below {straight:.2} without"
+    );
+}